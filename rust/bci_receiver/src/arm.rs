@@ -0,0 +1,76 @@
+//! Arm/disarm state: while disarmed the receiver only ever outputs neutral.
+
+/// Current arming state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArmState {
+    #[default]
+    Disarmed,
+    Armed,
+}
+
+/// Tracks the `arm` field of incoming messages.
+///
+/// Arming requires `arm: true` in `required` consecutive packets, so a
+/// single corrupted or stray packet can't arm the vehicle. Any `arm: false`
+/// disarms immediately; a packet without the field keeps the state but
+/// restarts the count.
+///
+/// After [`Arming::latch`] a streak only counts once the sender has stopped
+/// asking to be armed, so a sender that keeps sending `arm: true` can't
+/// release it without a fresh arm request.
+#[derive(Debug, Clone)]
+pub struct Arming {
+    required: u32,
+    streak: u32,
+    state: ArmState,
+    latched: bool,
+}
+
+impl Arming {
+    pub fn new(required: u32) -> Self {
+        Arming {
+            required: required.max(1),
+            streak: 0,
+            state: ArmState::Disarmed,
+            latched: false,
+        }
+    }
+
+    pub fn state(&self) -> ArmState {
+        self.state
+    }
+
+    /// Feeds the `arm` field of an accepted packet and returns the new state.
+    pub fn update(&mut self, arm: Option<bool>) -> ArmState {
+        match arm {
+            Some(true) if self.latched => {}
+            Some(true) => {
+                self.streak = self.streak.saturating_add(1);
+                if self.streak >= self.required {
+                    self.state = ArmState::Armed;
+                }
+            }
+            Some(false) => {
+                self.disarm();
+                self.latched = false;
+            }
+            None => {
+                self.streak = 0;
+                self.latched = false;
+            }
+        }
+        self.state
+    }
+
+    pub fn disarm(&mut self) {
+        self.streak = 0;
+        self.state = ArmState::Disarmed;
+    }
+
+    /// Disarms until the sender sends `arm: false` or leaves the field out,
+    /// then arms again as usual.
+    pub fn latch(&mut self) {
+        self.disarm();
+        self.latched = true;
+    }
+}
@@ -0,0 +1,141 @@
+//! Tokio-based receiver: the socket is read on one task and each sink runs on
+//! its own, fed from a broadcast channel, so a slow sink never delays reception.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::info;
+use tokio::net::UdpSocket;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinSet;
+use tokio::time::{self, MissedTickBehavior};
+
+use crate::config::Config;
+use crate::output::{self, Extrapolation, FrameSlot};
+use crate::pipeline::Pipeline;
+use crate::sink::AsyncControlSink;
+use crate::stats::{Reporter, Stats};
+use crate::ControlFrame;
+
+/// Frames buffered per sink. A sink that hasn't taken the previous frame by
+/// the next tick loses it, so it always resumes with the freshest one.
+const CHANNEL_DEPTH: usize = 1;
+
+/// Async equivalent of [`crate::receiver::run`], fanning frames out to every sink.
+///
+/// Returns once `shutdown` is set or a sink fails; all sinks are closed first.
+pub async fn run(
+    sock: &UdpSocket,
+    sinks: Vec<Box<dyn AsyncControlSink>>,
+    cfg: &Config,
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
+    let mut pipeline = Pipeline::new(cfg, Instant::now())?;
+    let slot = Arc::new(FrameSlot::new(cfg.failsafe_frame()));
+    let (tx, _) = broadcast::channel(CHANNEL_DEPTH);
+
+    let mut tasks = JoinSet::new();
+    for sink in sinks {
+        tasks.spawn(sink_task(
+            sink,
+            tx.subscribe(),
+            Arc::clone(&slot),
+            Arc::clone(pipeline.stats()),
+            cfg.failsafe_frame(),
+        ));
+    }
+    tasks.spawn(ticker(
+        tx,
+        Arc::clone(&slot),
+        Arc::clone(pipeline.stats()),
+        Duration::from_secs_f64(1.0 / cfg.output_hz),
+        cfg.extrapolation,
+    ));
+
+    let input = receive(sock, &mut pipeline, &slot, shutdown).await;
+    slot.close();
+
+    let mut res = input;
+    while let Some(joined) = tasks.join_next().await {
+        res = res.and(joined.map_err(io::Error::other).and_then(|r| r));
+    }
+    let stats = pipeline.finish()?;
+    info!("total: {}", stats.summary());
+    res
+}
+
+async fn receive(
+    sock: &UdpSocket,
+    pipeline: &mut Pipeline,
+    slot: &FrameSlot,
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
+    let timeout = pipeline.poll_interval();
+    let mut reporter = Reporter::new(Duration::from_secs(1), Instant::now());
+    let mut buf = pipeline.recv_buffer();
+    while !shutdown.load(Ordering::Relaxed) && !slot.is_closed() {
+        reporter.maybe_report(pipeline.stats(), Instant::now());
+        match time::timeout(timeout, sock.recv_from(&mut buf)).await {
+            Ok(Ok((_, from))) if !pipeline.accept_source(from) => {}
+            Ok(Ok((len, _))) => {
+                if let Some(frame) = pipeline.on_datagram(&buf[..len], Instant::now()) {
+                    slot.publish(frame);
+                    continue;
+                }
+            }
+            Ok(Err(e)) if e.kind() == io::ErrorKind::Interrupted => {}
+            Ok(Err(e)) => return Err(e),
+            Err(_elapsed) => {}
+        }
+        if let Some(frame) = pipeline.poll(Instant::now()) {
+            slot.publish(frame);
+        }
+    }
+    Ok(())
+}
+
+/// Broadcasts the latest frame at a fixed rate until the slot is closed.
+async fn ticker(
+    tx: broadcast::Sender<ControlFrame>,
+    slot: Arc<FrameSlot>,
+    stats: Arc<Stats>,
+    period: Duration,
+    extrapolation: Option<Extrapolation>,
+) -> io::Result<()> {
+    let mut interval = time::interval(period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    while !slot.is_closed() {
+        interval.tick().await;
+        // No receivers just means no sinks are configured.
+        let _ = tx.send(slot.output(extrapolation.as_ref(), Instant::now()));
+        stats.record_output();
+    }
+    Ok(())
+}
+
+async fn sink_task(
+    mut sink: Box<dyn AsyncControlSink>,
+    mut rx: broadcast::Receiver<ControlFrame>,
+    slot: Arc<FrameSlot>,
+    stats: Arc<Stats>,
+    safe: ControlFrame,
+) -> io::Result<()> {
+    let id = stats.register_sink(sink.name());
+    let res = loop {
+        match rx.recv().await {
+            Ok(frame) => {
+                if let Err(e) = sink.send(&frame).await {
+                    break Err(e);
+                }
+            }
+            // Fell behind: the oldest frames were dropped, carry on with the newest.
+            Err(RecvError::Lagged(n)) => output::record_dropped(&stats, id, sink.name(), n),
+            Err(RecvError::Closed) => break Ok(()),
+        }
+    };
+    // A failing sink stops the whole receiver, matching the threaded path.
+    slot.close();
+    res.and(sink.close(&safe).await)
+}
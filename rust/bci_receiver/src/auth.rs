@@ -0,0 +1,155 @@
+//! Optional shared-secret HMAC-SHA256 authentication of incoming packets.
+//!
+//! * JSON: the last member of the object is `"hmac": "<hex>"`. The tag covers
+//!   the message text with that member removed, i.e. the sender computes it
+//!   over `json.dumps(msg)` and then splices `, "hmac": "..."` in before the
+//!   closing brace.
+//! * Binary and MessagePack: the 32-byte tag is appended after the payload.
+
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::codec::{Format, BINARY_LEN};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const TAG_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthError {
+    /// No `hmac` member or trailing tag was found.
+    Missing,
+    /// The tag was present but malformed or did not match.
+    Mismatch,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::Missing => f.write_str("packet is not signed"),
+            AuthError::Mismatch => f.write_str("packet signature does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Verifies packets against a shared secret.
+#[derive(Clone)]
+pub struct Authenticator {
+    mac: HmacSha256,
+}
+
+impl fmt::Debug for Authenticator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Authenticator { .. }")
+    }
+}
+
+impl Authenticator {
+    pub fn new(secret: &[u8]) -> Self {
+        Authenticator {
+            mac: HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length"),
+        }
+    }
+
+    /// Checks the tag on `bytes` and returns the part to hand to the decoder.
+    pub fn verify<'a>(&self, bytes: &'a [u8], format: Format) -> Result<&'a [u8], AuthError> {
+        let binary = match format {
+            Format::Json => false,
+            Format::Binary | Format::MsgPack => true,
+            Format::Auto => bytes.len() == BINARY_LEN + TAG_LEN,
+        };
+        if binary {
+            self.verify_binary(bytes)
+        } else {
+            self.verify_json(bytes).map(|()| bytes)
+        }
+    }
+
+    fn verify_binary<'a>(&self, bytes: &'a [u8]) -> Result<&'a [u8], AuthError> {
+        if bytes.len() <= TAG_LEN {
+            return Err(AuthError::Missing);
+        }
+        let (payload, tag) = bytes.split_at(bytes.len() - TAG_LEN);
+        let mut mac = self.mac.clone();
+        mac.update(payload);
+        mac.verify_slice(tag).map_err(|_| AuthError::Mismatch)?;
+        Ok(payload)
+    }
+
+    fn verify_json(&self, bytes: &[u8]) -> Result<(), AuthError> {
+        let (body, tag_hex) = split_json_tag(bytes).ok_or(AuthError::Missing)?;
+        let tag = decode_hex(tag_hex).ok_or(AuthError::Mismatch)?;
+        let mut mac = self.mac.clone();
+        mac.update(body);
+        mac.update(b"}");
+        mac.verify_slice(&tag).map_err(|_| AuthError::Mismatch)
+    }
+
+    /// Signs a JSON message (without `hmac`) the way senders are expected to.
+    ///
+    /// `body` must be a single JSON object ending in `}`.
+    pub fn sign_json(&self, body: &str) -> String {
+        let body = body.trim_end();
+        let mut mac = self.mac.clone();
+        mac.update(body.as_bytes());
+        let tag = encode_hex(&mac.finalize().into_bytes());
+        let open = body.strip_suffix('}').unwrap_or(body).trim_end();
+        format!("{open}, \"hmac\": \"{tag}\"}}")
+    }
+
+    /// Appends the tag to a binary payload.
+    pub fn sign_binary(&self, payload: &[u8]) -> Vec<u8> {
+        let mut mac = self.mac.clone();
+        mac.update(payload);
+        let mut out = payload.to_vec();
+        out.extend_from_slice(&mac.finalize().into_bytes());
+        out
+    }
+}
+
+/// Splits `{..., "hmac": "abcd"}` into the text before the separating comma
+/// and the hex tag.
+fn split_json_tag(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    const KEY: &[u8] = b"\"hmac\"";
+    let key_at = bytes.windows(KEY.len()).rposition(|w| w == KEY)?;
+    let comma = bytes[..key_at]
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .filter(|&i| bytes[i] == b',')?;
+
+    let rest = &bytes[key_at + KEY.len()..];
+    let rest = trim_start(rest).strip_prefix(b":")?;
+    let rest = trim_start(rest).strip_prefix(b"\"")?;
+    let end = rest.iter().position(|&b| b == b'"')?;
+    let tail = trim_start(&rest[end + 1..]);
+    if trim_start(tail.strip_prefix(b"}")?).is_empty() {
+        Some((&bytes[..comma], &rest[..end]))
+    } else {
+        None
+    }
+}
+
+fn trim_start(b: &[u8]) -> &[u8] {
+    let n = b.iter().take_while(|c| c.is_ascii_whitespace()).count();
+    &b[n..]
+}
+
+fn decode_hex(hex: &[u8]) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    hex.chunks(2)
+        .map(|pair| {
+            let s = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(s, 16).ok()
+        })
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
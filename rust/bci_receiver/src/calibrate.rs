@@ -0,0 +1,175 @@
+//! `--calibrate`: measures the range one user's signal covers on each axis
+//! and stores it in the profile's `[calibration]` section.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::net::UdpSocket;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+
+use crate::auth::Authenticator;
+use crate::cipher::Cipher;
+use crate::config::Config;
+use crate::{AxisRange, Calibration};
+
+/// Axes that moved less than this are left uncalibrated; stretching them
+/// would mostly amplify noise.
+const MIN_SPAN: f32 = 0.1;
+
+/// Collects packets for `duration` and returns the range seen on each axis.
+///
+/// Source filtering, decryption, authentication and the wire format follow `cfg`; the
+/// values recorded are the raw ones, before any calibration, inversion or
+/// curve is applied.
+pub fn record(
+    sock: &UdpSocket,
+    cfg: &Config,
+    duration: Duration,
+    shutdown: &AtomicBool,
+) -> io::Result<Calibration> {
+    let cipher = cfg.enc_key.as_ref().map(|k| Cipher::new(k.as_bytes()));
+    let auth = cfg
+        .secret
+        .as_ref()
+        .map(|s| Authenticator::new(s.as_bytes()));
+    let source = cfg.source.map(|ip| ip.to_canonical());
+    sock.set_read_timeout(Some(Duration::from_millis(200)))?;
+    info!(
+        "calibrating for {} s: move every axis through its full range",
+        duration.as_secs()
+    );
+
+    let deadline = Instant::now() + duration;
+    let mut seen: [Option<(f32, f32)>; 5] = [None; 5];
+    let mut packets = 0u64;
+    // One spare byte tells a truncated datagram from one that just fits.
+    let mut buf = vec![0u8; cfg.recv_buffer + 1];
+    while Instant::now() < deadline && !shutdown.load(Ordering::Relaxed) {
+        let (len, from) = match sock.recv_from(&mut buf) {
+            Ok(r) => r,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock
+                        | io::ErrorKind::TimedOut
+                        | io::ErrorKind::Interrupted
+                ) =>
+            {
+                continue
+            }
+            Err(e) => return Err(e),
+        };
+        if len > cfg.recv_buffer || source.is_some_and(|ip| from.ip().to_canonical() != ip) {
+            continue;
+        }
+        let plaintext;
+        let bytes = match &cipher {
+            Some(cipher) => match cipher.open(&buf[..len]) {
+                Ok(p) => {
+                    plaintext = p;
+                    &plaintext[..]
+                }
+                Err(_) => continue,
+            },
+            None => &buf[..len],
+        };
+        let bytes = match &auth {
+            Some(auth) => match auth.verify(bytes, cfg.format) {
+                Ok(payload) => payload,
+                Err(_) => continue,
+            },
+            None => bytes,
+        };
+        let Ok(frame) = cfg.format.parse(bytes).and_then(|m| m.to_frame()) else {
+            continue;
+        };
+        let values = [
+            frame.yaw,
+            frame.altitude,
+            frame.pitch,
+            frame.roll,
+            frame.throttle,
+        ];
+        for (range, v) in seen.iter_mut().zip(values) {
+            if !v.is_finite() {
+                continue;
+            }
+            let v = v.clamp(-1.0, 1.0);
+            *range = Some(match *range {
+                Some((lo, hi)) => (lo.min(v), hi.max(v)),
+                None => (v, v),
+            });
+        }
+        packets += 1;
+    }
+    if packets == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "no valid packets received during calibration",
+        ));
+    }
+
+    let names = ["yaw", "altitude", "pitch", "roll", "throttle"];
+    let mut ranges = [AxisRange::FULL; 5];
+    for ((range, name), seen) in ranges.iter_mut().zip(names).zip(seen) {
+        match seen {
+            Some((min, max)) if max - min >= MIN_SPAN => {
+                info!("{name}: {min:.3} .. {max:.3}");
+                *range = AxisRange { min, max };
+            }
+            _ => warn!("{name} barely moved, leaving it uncalibrated"),
+        }
+    }
+    info!("calibrated from {packets} packets");
+    let [yaw, altitude, pitch, roll, throttle] = ranges;
+    Ok(Calibration {
+        yaw,
+        altitude,
+        pitch,
+        roll,
+        throttle,
+    })
+}
+
+/// Replaces the `[calibration]` section of the profile at `path` (creating
+/// the file if needed), keeping every other line as it was.
+pub fn save(path: impl AsRef<Path>, cal: &Calibration) -> io::Result<()> {
+    let path = path.as_ref();
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut out = String::new();
+    let mut in_calibration = false;
+    for line in text.lines() {
+        if let Some(header) = line.trim().strip_prefix('[') {
+            let name = header
+                .trim_start_matches('[')
+                .split(']')
+                .next()
+                .unwrap_or("")
+                .trim();
+            in_calibration = name == "calibration" || name.starts_with("calibration.");
+        }
+        if !in_calibration {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    let mut out = out.trim_end().to_owned();
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+    out.push_str("[calibration]\n");
+    for (name, r) in cal.axes() {
+        if r != AxisRange::FULL {
+            let _ = writeln!(out, "{name} = {{ min = {:?}, max = {:?} }}", r.min, r.max);
+        }
+    }
+    fs::write(path, out)
+}
@@ -0,0 +1,81 @@
+//! Optional AES-256-GCM encryption of whole datagrams, for confidentiality on
+//! untrusted networks.
+//!
+//! A packet is `nonce (12 bytes) || ciphertext || tag (16 bytes)`, where the
+//! plaintext is exactly what would be sent unencrypted (including any HMAC,
+//! see [`crate::auth`]). The key is the SHA-256 digest of the shared key
+//! string, so senders only need to agree on the string. A nonce must never be
+//! reused with the same key; a counter or 12 random bytes per packet both work.
+
+use std::fmt;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use sha2::{Digest, Sha256};
+
+pub const NONCE_LEN: usize = 12;
+pub const TAG_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherError {
+    /// Shorter than a nonce and tag, so it can't be an encrypted packet.
+    Truncated(usize),
+    /// The tag did not verify: wrong key, tampered or plaintext packet.
+    Decrypt,
+}
+
+impl fmt::Display for CipherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CipherError::Truncated(len) => {
+                write!(f, "{len}-byte packet is too short to be encrypted")
+            }
+            CipherError::Decrypt => f.write_str("packet does not decrypt with the configured key"),
+        }
+    }
+}
+
+impl std::error::Error for CipherError {}
+
+/// Decrypts packets with a shared key.
+#[derive(Clone)]
+pub struct Cipher {
+    aead: Aes256Gcm,
+}
+
+impl fmt::Debug for Cipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Cipher { .. }")
+    }
+}
+
+impl Cipher {
+    pub fn new(key: &[u8]) -> Self {
+        let key = Sha256::digest(key);
+        Cipher {
+            aead: Aes256Gcm::new(&key),
+        }
+    }
+
+    /// Verifies and decrypts `bytes`, returning the plaintext datagram.
+    pub fn open(&self, bytes: &[u8]) -> Result<Vec<u8>, CipherError> {
+        if bytes.len() < NONCE_LEN + TAG_LEN {
+            return Err(CipherError::Truncated(bytes.len()));
+        }
+        let (nonce, sealed) = bytes.split_at(NONCE_LEN);
+        self.aead
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| CipherError::Decrypt)
+    }
+
+    /// Encrypts a datagram the way senders are expected to.
+    pub fn seal(&self, nonce: &[u8; NONCE_LEN], plaintext: &[u8]) -> Vec<u8> {
+        let sealed = self
+            .aead
+            .encrypt(Nonce::from_slice(nonce), plaintext)
+            .expect("AES-GCM encrypts any datagram-sized payload");
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&sealed);
+        out
+    }
+}
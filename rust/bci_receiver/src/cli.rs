@@ -0,0 +1,464 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+
+use bci_receiver::config::Route;
+use bci_receiver::filter::FilterKind;
+use bci_receiver::jitter::JitterConfig;
+use bci_receiver::output::Extrapolation;
+use bci_receiver::{AltMode, AltRange, ConfidenceGate, Config, Format, GateMode, Invert};
+use clap::Parser;
+
+/// UDP receiver for the BCI-Flystick control stream.
+///
+/// Settings come from the built-in defaults, then the --config profile, then
+/// the flags below, each overriding the previous.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// TOML profile to load before applying the flags below.
+    #[arg(long, value_name = "PATH", env = "BCI_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Local address to receive BCI packets on, e.g. 0.0.0.0:5005 or [::1]:5005 [default: 127.0.0.1:5005].
+    #[arg(long, env = "BCI_BIND", value_parser = parse_addr)]
+    pub bind: Option<SocketAddr>,
+
+    /// With an IPv6 --bind address such as [::]:5005, accept IPv4 packets as well.
+    #[arg(long)]
+    pub dual_stack: bool,
+
+    /// Only accept packets sent from this IP address; others are dropped and counted.
+    #[arg(long, value_name = "IP")]
+    pub source: Option<IpAddr>,
+
+    /// Forward frames as MAVLink to this flight controller address.
+    #[arg(long, value_name = "ADDR:PORT", value_parser = parse_addr)]
+    pub mavlink: Option<SocketAddr>,
+
+    /// Fly X-Plane by sending DATA packets to this address (the sim listens on port 49000 by default).
+    #[arg(long, value_name = "ADDR:PORT", value_parser = parse_addr)]
+    pub xplane: Option<SocketAddr>,
+
+    /// Publish frames as geometry_msgs/Twist on this ROS2 topic.
+    #[cfg(feature = "ros2")]
+    #[arg(long, value_name = "TOPIC")]
+    pub ros2_topic: Option<String>,
+
+    /// Node name used by --ros2-topic [default: bci_flystick].
+    #[cfg(feature = "ros2")]
+    #[arg(long, value_name = "NAME")]
+    pub ros2_node: Option<String>,
+
+    /// Expose frames as a virtual joystick with this device name (Linux, needs /dev/uinput access).
+    #[cfg(target_os = "linux")]
+    #[arg(long, value_name = "NAME", num_args = 0..=1, default_missing_value = "BCI-Flystick")]
+    pub uinput: Option<String>,
+
+    /// Send failsafe controls after this many milliseconds without a valid packet [default: 300].
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub failsafe_ms: Option<u64>,
+
+    /// Ramp from the failsafe controls back to live input over this many milliseconds [default: 0].
+    #[arg(long, value_name = "MS")]
+    pub recover_ms: Option<u64>,
+
+    /// Rate at which the latest frame is sent to the sink, independent of the input rate [default: 50].
+    #[arg(long, value_name = "HZ", value_parser = parse_hz)]
+    pub output_hz: Option<f64>,
+
+    /// Wire format of incoming packets: json, binary (24-byte little-endian), msgpack or auto (json/binary) [default: json].
+    #[arg(long)]
+    pub format: Option<Format>,
+
+    /// Largest datagram accepted; longer ones are dropped and counted as oversized [default: 2048].
+    #[arg(long, value_name = "BYTES", value_parser = clap::value_parser!(u16).range(64..))]
+    pub recv_buffer: Option<u16>,
+
+    /// Expo exponent for yaw (1.0 = linear, >1 softer around center).
+    #[arg(long, value_name = "GAMMA", value_parser = parse_expo)]
+    pub yaw_expo: Option<f32>,
+
+    /// Expo exponent for pitch.
+    #[arg(long, value_name = "GAMMA", value_parser = parse_expo)]
+    pub pitch_expo: Option<f32>,
+
+    /// Expo exponent for roll.
+    #[arg(long, value_name = "GAMMA", value_parser = parse_expo)]
+    pub roll_expo: Option<f32>,
+
+    /// Expo exponent for throttle.
+    #[arg(long, value_name = "GAMMA", value_parser = parse_expo)]
+    pub throttle_expo: Option<f32>,
+
+    /// Yaw inputs with a magnitude below this produce exactly zero.
+    #[arg(long, value_name = "X", value_parser = parse_deadzone)]
+    pub yaw_deadzone: Option<f32>,
+
+    /// Pitch deadzone threshold.
+    #[arg(long, value_name = "X", value_parser = parse_deadzone)]
+    pub pitch_deadzone: Option<f32>,
+
+    /// Roll deadzone threshold.
+    #[arg(long, value_name = "X", value_parser = parse_deadzone)]
+    pub roll_deadzone: Option<f32>,
+
+    /// Throttle deadzone threshold (around mid-throttle).
+    #[arg(long, value_name = "X", value_parser = parse_deadzone)]
+    pub throttle_deadzone: Option<f32>,
+
+    /// Comma-separated axes to flip before deadzone/expo, e.g. yaw,pitch (replaces the config's list).
+    #[arg(long, value_name = "AXES", value_parser = Invert::parse_list)]
+    pub invert: Option<Invert>,
+
+    /// Map normalized altitude onto this height range in meters; MAVLink then sends it as a position setpoint.
+    #[arg(long, num_args = 2, value_names = ["MIN", "MAX"], allow_negative_numbers = true, value_parser = parse_meters)]
+    pub alt_range: Option<Vec<f32>>,
+
+    /// absolute: the altitude axis is the height; rate: it is a climb rate integrated into the height [default: absolute].
+    #[arg(long)]
+    pub alt_mode: Option<AltMode>,
+
+    /// Climb rate at full altitude deflection in --alt-mode rate [default: 1.0].
+    #[arg(long, value_name = "M/S", value_parser = parse_climb_rate)]
+    pub climb_rate: Option<f32>,
+
+    /// Gate frames whose `confidence` is below this value (messages without it always pass).
+    #[arg(long, value_name = "X", value_parser = parse_confidence)]
+    pub min_confidence: Option<f32>,
+
+    /// What to do with frames below the confidence threshold: zero, attenuate or pass (count only) [default: zero].
+    #[arg(long)]
+    pub conf_mode: Option<GateMode>,
+
+    /// Stay neutral until N consecutive packets carry `"arm": true`; `"arm": false` or a failsafe disarms.
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "5", value_parser = clap::value_parser!(u32).range(1..))]
+    pub require_arm: Option<u32>,
+
+    /// Smoothing: none, ema (all axes, see --alpha) or kalman (altitude; EMA on the rest) [default: ema].
+    #[arg(long)]
+    pub filter: Option<FilterKind>,
+
+    /// Low-pass weight of each new frame in (0, 1]; 1.0 disables smoothing.
+    #[arg(long, value_parser = parse_alpha)]
+    pub alpha: Option<f32>,
+
+    /// Kalman process noise (acceleration variance); higher follows changes faster [default: 0.5].
+    #[arg(long, value_name = "Q", value_parser = parse_noise)]
+    pub kalman_q: Option<f32>,
+
+    /// Kalman measurement noise (sample variance); higher smooths more [default: 0.05].
+    #[arg(long, value_name = "R", value_parser = parse_noise)]
+    pub kalman_r: Option<f32>,
+
+    /// Maximum yaw change per second after smoothing (full range is 2.0).
+    #[arg(long, value_name = "UNITS/S", value_parser = parse_slew)]
+    pub yaw_slew: Option<f32>,
+
+    /// Maximum throttle change per second after smoothing.
+    #[arg(long, value_name = "UNITS/S", value_parser = parse_slew)]
+    pub throttle_slew: Option<f32>,
+
+    /// Extrapolate the last trend for up to MS milliseconds when packets are late [default: 100 if given without a value].
+    #[arg(long, value_name = "MS", num_args = 0..=1, default_missing_value = "100")]
+    pub extrapolate_ms: Option<u64>,
+
+    /// Maximum distance (per axis) extrapolation may move away from the last received frame [default: 0.2].
+    #[arg(long, value_name = "X", requires = "extrapolate_ms", value_parser = parse_max_delta)]
+    pub extrapolate_max: Option<f32>,
+
+    /// Hold packets this long to reorder them by `ts` before filtering [default: off].
+    #[arg(long, value_name = "MS")]
+    pub jitter_ms: Option<u64>,
+
+    /// Most packets the jitter buffer holds at once [default: 3].
+    #[arg(long, value_name = "N", requires = "jitter_ms")]
+    pub jitter_depth: Option<usize>,
+
+    /// Append every received message, with its arrival time, to this JSONL file.
+    #[arg(long, value_name = "PATH")]
+    pub record: Option<PathBuf>,
+
+    /// Also write every emitted frame as a CSV row to this file; `recv_ts` is the emit time.
+    #[arg(long, value_name = "PATH")]
+    pub csv: Option<PathBuf>,
+
+    /// Broadcast every emitted frame as JSON to WebSocket clients on this address.
+    #[arg(long, value_name = "ADDR:PORT", value_parser = parse_addr)]
+    pub ws: Option<SocketAddr>,
+
+    /// Send frames with `"target": ID` to the MAVLink vehicle at ADDR:PORT; repeat per aircraft.
+    #[arg(long, value_name = "ID=ADDR:PORT", value_parser = parse_route)]
+    pub route: Vec<Route>,
+
+    /// Serve Prometheus metrics over HTTP on this address.
+    #[arg(long, value_name = "ADDR:PORT", value_parser = parse_addr)]
+    pub metrics: Option<SocketAddr>,
+
+    /// Shared secret for HMAC-SHA256 packet authentication; unsigned packets are dropped.
+    #[arg(long, env = "BCI_SECRET", hide_env_values = true)]
+    pub secret: Option<String>,
+
+    /// Shared key for AES-256-GCM payload encryption; packets that don't decrypt are dropped.
+    #[arg(long, env = "BCI_ENC_KEY", hide_env_values = true)]
+    pub enc_key: Option<String>,
+
+    /// Check that every sink is reachable before receiving (MAVLink waits for a heartbeat).
+    #[arg(long)]
+    pub selftest: bool,
+
+    /// Record each axis's range for SECS seconds and save it to the --config profile, then exit [default: 10 if given without a value].
+    #[arg(long, value_name = "SECS", num_args = 0..=1, default_missing_value = "10", requires = "config", conflicts_with = "replay")]
+    pub calibrate: Option<u64>,
+
+    /// Replay a recorded JSONL file instead of listening on UDP.
+    #[arg(long, value_name = "PATH", conflicts_with = "record")]
+    pub replay: Option<PathBuf>,
+
+    /// Playback speed multiplier for --replay.
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, requires = "replay", value_parser = parse_speed)]
+    pub speed: f64,
+
+    /// Decode, validate and count packets but open no outputs; frames are only logged at debug level.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl Cli {
+    /// Loads the --config profile (or the defaults) and applies the flags on top.
+    pub fn config(&self) -> io::Result<Config> {
+        let mut cfg = match &self.config {
+            // --calibrate creates the profile it writes to.
+            Some(path) if self.calibrate.is_some() && !path.exists() => Config::default(),
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+
+        fn set<T: Clone>(dst: &mut T, flag: &Option<T>) {
+            if let Some(v) = flag {
+                *dst = v.clone();
+            }
+        }
+        fn set_opt<T: Clone>(dst: &mut Option<T>, flag: &Option<T>) {
+            if flag.is_some() {
+                dst.clone_from(flag);
+            }
+        }
+
+        set(&mut cfg.bind, &self.bind);
+        cfg.dual_stack |= self.dual_stack;
+        set_opt(&mut cfg.source, &self.source);
+        set(&mut cfg.failsafe_ms, &self.failsafe_ms);
+        set(&mut cfg.recover_ms, &self.recover_ms);
+        set(&mut cfg.output_hz, &self.output_hz);
+        set(&mut cfg.format, &self.format);
+        set(&mut cfg.recv_buffer, &self.recv_buffer.map(usize::from));
+        set(&mut cfg.curves.yaw.expo, &self.yaw_expo);
+        set(&mut cfg.curves.pitch.expo, &self.pitch_expo);
+        set(&mut cfg.curves.roll.expo, &self.roll_expo);
+        set(&mut cfg.curves.throttle.expo, &self.throttle_expo);
+        set(&mut cfg.curves.yaw.deadzone, &self.yaw_deadzone);
+        set(&mut cfg.curves.pitch.deadzone, &self.pitch_deadzone);
+        set(&mut cfg.curves.roll.deadzone, &self.roll_deadzone);
+        set(&mut cfg.curves.throttle.deadzone, &self.throttle_deadzone);
+        set(&mut cfg.invert, &self.invert);
+        if let Some(r) = &self.alt_range {
+            cfg.alt_range = Some(match cfg.alt_range {
+                Some(prev) => AltRange {
+                    min_m: r[0],
+                    max_m: r[1],
+                    ..prev
+                },
+                None => AltRange::new(r[0], r[1]),
+            });
+        }
+        if self.alt_mode.is_some() || self.climb_rate.is_some() {
+            let Some(r) = &mut cfg.alt_range else {
+                return Err(invalid(
+                    "--alt-mode and --climb-rate need --alt-range or an [alt_range] section in the config",
+                ));
+            };
+            set(&mut r.mode, &self.alt_mode);
+            set(&mut r.climb_rate, &self.climb_rate);
+        }
+        if let Some(threshold) = self.min_confidence {
+            let mode = cfg.gate.map(|g| g.mode).unwrap_or_default();
+            cfg.gate = Some(ConfidenceGate { threshold, mode });
+        }
+        if let Some(mode) = self.conf_mode {
+            match &mut cfg.gate {
+                Some(g) => g.mode = mode,
+                None => {
+                    return Err(invalid(
+                        "--conf-mode needs --min-confidence or a [gate] section in the config",
+                    ))
+                }
+            }
+        }
+        set_opt(&mut cfg.arm_packets, &self.require_arm);
+        set(&mut cfg.filter, &self.filter);
+        set(&mut cfg.alpha, &self.alpha);
+        set(&mut cfg.kalman.process_noise, &self.kalman_q);
+        set(&mut cfg.kalman.measurement_noise, &self.kalman_r);
+        set_opt(&mut cfg.slew.yaw, &self.yaw_slew);
+        set_opt(&mut cfg.slew.throttle, &self.throttle_slew);
+        if let Some(horizon_ms) = self.extrapolate_ms {
+            cfg.extrapolation = Some(Extrapolation {
+                horizon_ms,
+                ..cfg.extrapolation.unwrap_or_default()
+            });
+        }
+        if let (Some(x), Some(max)) = (&mut cfg.extrapolation, self.extrapolate_max) {
+            x.max_delta = max;
+        }
+        if let Some(delay_ms) = self.jitter_ms {
+            cfg.jitter = Some(JitterConfig {
+                delay_ms,
+                ..cfg.jitter.unwrap_or_default()
+            });
+        }
+        if let (Some(j), Some(depth)) = (&mut cfg.jitter, self.jitter_depth) {
+            j.depth = depth;
+        }
+        set_opt(&mut cfg.record, &self.record);
+        set_opt(&mut cfg.metrics, &self.metrics);
+        set_opt(&mut cfg.secret, &self.secret);
+        set_opt(&mut cfg.enc_key, &self.enc_key);
+        cfg.selftest |= self.selftest;
+
+        set_opt(&mut cfg.sinks.mavlink, &self.mavlink);
+        set_opt(&mut cfg.sinks.xplane, &self.xplane);
+        #[cfg(feature = "ros2")]
+        {
+            set_opt(&mut cfg.sinks.ros2_topic, &self.ros2_topic);
+            set_opt(&mut cfg.sinks.ros2_node, &self.ros2_node);
+        }
+        #[cfg(target_os = "linux")]
+        set_opt(&mut cfg.sinks.uinput, &self.uinput);
+        set_opt(&mut cfg.sinks.csv, &self.csv);
+        set_opt(&mut cfg.sinks.ws, &self.ws);
+        if !self.route.is_empty() {
+            cfg.sinks.routes.clone_from(&self.route);
+        }
+
+        cfg.validate().map_err(invalid)?;
+        Ok(cfg)
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg.into())
+}
+
+fn parse_addr(s: &str) -> Result<SocketAddr, String> {
+    s.parse().map_err(|_| {
+        format!("`{s}` is not a valid socket address, expected <ip>:<port> such as 0.0.0.0:5005 or [::]:5005")
+    })
+}
+
+fn parse_route(s: &str) -> Result<Route, String> {
+    let (target, addr) = s.split_once('=').ok_or_else(|| {
+        format!("`{s}` is not a route, expected <id>=<ip>:<port> such as 2=192.168.1.12:14550")
+    })?;
+    Ok(Route {
+        target: target
+            .parse()
+            .map_err(|_| format!("`{target}` is not a valid target id, expected 0..=255"))?,
+        mavlink: parse_addr(addr)?,
+    })
+}
+
+fn parse_expo(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(v),
+        _ => Err(format!(
+            "`{s}` is not a valid expo, expected a positive number"
+        )),
+    }
+}
+
+fn parse_deadzone(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(v) if (0.0..1.0).contains(&v) => Ok(v),
+        _ => Err(format!(
+            "`{s}` is not a valid deadzone, expected a value in [0, 1)"
+        )),
+    }
+}
+
+fn parse_alpha(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(v) if v > 0.0 && v <= 1.0 => Ok(v),
+        _ => Err(format!(
+            "`{s}` is not a valid alpha, expected a value in (0, 1]"
+        )),
+    }
+}
+
+fn parse_meters(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(v) if v.is_finite() => Ok(v),
+        _ => Err(format!("`{s}` is not a valid height, expected meters")),
+    }
+}
+
+fn parse_confidence(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(v) if v > 0.0 && v <= 1.0 => Ok(v),
+        _ => Err(format!(
+            "`{s}` is not a valid confidence, expected a value in (0, 1]"
+        )),
+    }
+}
+
+fn parse_slew(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(v),
+        _ => Err(format!(
+            "`{s}` is not a valid slew rate, expected a positive number of units per second"
+        )),
+    }
+}
+
+fn parse_noise(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(v),
+        _ => Err(format!(
+            "`{s}` is not a valid noise variance, expected a positive number"
+        )),
+    }
+}
+
+fn parse_max_delta(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(v) if (0.0..=2.0).contains(&v) => Ok(v),
+        _ => Err(format!(
+            "`{s}` is not a valid extrapolation limit, expected a value in [0, 2]"
+        )),
+    }
+}
+
+fn parse_climb_rate(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(v),
+        _ => Err(format!(
+            "`{s}` is not a valid climb rate, expected a positive number of m/s"
+        )),
+    }
+}
+
+fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(v) if v.is_finite() && v > 0.0 => Ok(v),
+        _ => Err(format!(
+            "`{s}` is not a valid speed, expected a positive number"
+        )),
+    }
+}
+
+fn parse_hz(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(v) if v.is_finite() && (0.1..=1000.0).contains(&v) => Ok(v),
+        _ => Err(format!("`{s}` is not a valid rate, expected 0.1..=1000 Hz")),
+    }
+}
@@ -0,0 +1,153 @@
+//! Wire formats accepted on the UDP socket.
+//!
+//! All formats decode into [`BciMsg`], so optional fields fall back the same
+//! way whichever one a sender uses. New formats only need a variant here.
+//!
+//! The binary layout predates `roll`, so binary senders always fly with roll 0.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::BciMsg;
+
+/// Size of a binary packet: yaw, altitude, pitch, throttle as f32 and ts as f64, little-endian.
+pub const BINARY_LEN: usize = 24;
+
+/// How datagrams are interpreted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    Binary,
+    /// A MessagePack map with the same keys as the JSON object.
+    MsgPack,
+    /// Binary for packets of exactly [`BINARY_LEN`] bytes, JSON otherwise.
+    /// Unambiguous because the shortest valid JSON message is longer than that.
+    Auto,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "binary" => Ok(Format::Binary),
+            "msgpack" => Ok(Format::MsgPack),
+            "auto" => Ok(Format::Auto),
+            _ => Err(format!(
+                "unknown format `{s}`, expected json, binary, msgpack or auto"
+            )),
+        }
+    }
+}
+
+impl Format {
+    pub fn parse(self, bytes: &[u8]) -> Result<BciMsg, DecodeError> {
+        match self {
+            Format::Json => parse_json(bytes),
+            Format::Binary => parse_binary(bytes),
+            Format::MsgPack => parse_msgpack(bytes),
+            Format::Auto if bytes.len() == BINARY_LEN => parse_binary(bytes),
+            Format::Auto => parse_json(bytes),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DecodeError {
+    Utf8(std::str::Utf8Error),
+    Json(serde_json::Error),
+    MsgPack(rmp_serde::decode::Error),
+    BinaryLength(usize),
+    /// A field was NaN or infinite; the whole packet is rejected.
+    NonFinite(&'static str),
+    /// A field required by the message's schema version was absent.
+    Missing(&'static str),
+    /// The message declares a schema version newer than [`crate::LATEST_VERSION`].
+    Version(u32),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Utf8(e) => write!(f, "payload is not valid UTF-8: {e}"),
+            DecodeError::Json(e) => write!(f, "invalid JSON message: {e}"),
+            DecodeError::MsgPack(e) => write!(f, "invalid MessagePack message: {e}"),
+            DecodeError::BinaryLength(n) => {
+                write!(f, "binary packet must be {BINARY_LEN} bytes, got {n}")
+            }
+            DecodeError::NonFinite(field) => write!(f, "`{field}` is not a finite number"),
+            DecodeError::Missing(field) => {
+                write!(f, "`{field}` is required by this message version")
+            }
+            DecodeError::Version(v) => write!(
+                f,
+                "unsupported message version {v}, expected 1..={}",
+                crate::LATEST_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Utf8(e) => Some(e),
+            DecodeError::Json(e) => Some(e),
+            DecodeError::MsgPack(e) => Some(e),
+            DecodeError::BinaryLength(_)
+            | DecodeError::NonFinite(_)
+            | DecodeError::Missing(_)
+            | DecodeError::Version(_) => None,
+        }
+    }
+}
+
+pub fn parse_json(bytes: &[u8]) -> Result<BciMsg, DecodeError> {
+    let txt = std::str::from_utf8(bytes).map_err(DecodeError::Utf8)?;
+    serde_json::from_str(txt).map_err(DecodeError::Json)
+}
+
+pub fn parse_msgpack(bytes: &[u8]) -> Result<BciMsg, DecodeError> {
+    rmp_serde::from_slice(bytes).map_err(DecodeError::MsgPack)
+}
+
+pub fn parse_binary(bytes: &[u8]) -> Result<BciMsg, DecodeError> {
+    let b: &[u8; BINARY_LEN] = bytes
+        .try_into()
+        .map_err(|_| DecodeError::BinaryLength(bytes.len()))?;
+    let f32_at = |i: usize| f32::from_le_bytes([b[i], b[i + 1], b[i + 2], b[i + 3]]);
+    let mut ts = [0u8; 8];
+    ts.copy_from_slice(&b[16..24]);
+    Ok(BciMsg {
+        yaw: f32_at(0),
+        altitude: f32_at(4),
+        pitch: Some(f32_at(8)),
+        roll: None,
+        throttle: Some(f32_at(12)),
+        speed: None,
+        ts: f64::from_le_bytes(ts),
+        confidence: None,
+        arm: None,
+        target: None,
+        event: None,
+        seq: None,
+        v: None,
+    })
+}
+
+/// Encodes `msg` in the binary layout, resolving pitch/throttle like the decoder does.
+pub fn encode_binary(msg: &BciMsg) -> [u8; BINARY_LEN] {
+    let frame = crate::ControlFrame::from(msg);
+    let mut out = [0u8; BINARY_LEN];
+    out[0..4].copy_from_slice(&frame.yaw.to_le_bytes());
+    out[4..8].copy_from_slice(&frame.altitude.to_le_bytes());
+    out[8..12].copy_from_slice(&frame.pitch.to_le_bytes());
+    out[12..16].copy_from_slice(&frame.throttle.to_le_bytes());
+    out[16..24].copy_from_slice(&frame.ts.to_le_bytes());
+    out
+}
@@ -0,0 +1,303 @@
+//! Receiver settings, loadable from a TOML profile.
+
+use std::fs;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::failsafe::FailsafeFrame;
+use crate::filter::{FilterKind, KalmanParams, SlewLimits};
+use crate::jitter::JitterConfig;
+use crate::output::Extrapolation;
+use crate::sink::MavlinkSink;
+use crate::{
+    AltRange, AxisCurve, Calibration, ConfidenceGate, ControlFrame, Curves, Format, Invert,
+};
+
+/// Runtime parameters for the receive loop.
+///
+/// Every field has a default, so an empty profile is a valid configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Local address to receive BCI packets on.
+    pub bind: SocketAddr,
+    /// Let an IPv6 `bind` address accept IPv4 packets too.
+    pub dual_stack: bool,
+    /// Only accept datagrams sent from this address.
+    pub source: Option<IpAddr>,
+    /// Emit the failsafe frame when no valid packet arrived for this long.
+    pub failsafe_ms: u64,
+    /// Frame emitted on timeout or while disarmed.
+    pub failsafe: FailsafeFrame,
+    /// Blend from the failsafe frame back to live input over this long; 0 switches at once.
+    pub recover_ms: u64,
+    /// Rate at which the latest frame is pushed to the sink, independent of input.
+    pub output_hz: f64,
+    pub format: Format,
+    /// Largest datagram accepted, in bytes; longer ones are dropped as oversized.
+    pub recv_buffer: usize,
+    /// Per-user input ranges, written by `--calibrate`.
+    pub calibration: Calibration,
+    pub curves: Curves,
+    /// Axes to flip before the curves are applied.
+    pub invert: Invert,
+    /// Maps the normalized altitude onto a height in meters (`ControlFrame::altitude_m`).
+    pub alt_range: Option<AltRange>,
+    /// Neutralizes or attenuates frames the classifier was unsure about.
+    pub gate: Option<ConfidenceGate>,
+    /// Require `arm: true` in this many consecutive packets before leaving
+    /// neutral; `None` flies without arming.
+    pub arm_packets: Option<u32>,
+    /// Which smoothing to apply.
+    pub filter: FilterKind,
+    /// EMA weight of the newest frame; 1.0 disables smoothing.
+    pub alpha: f32,
+    /// Altitude filter tuning for `filter = "kalman"`.
+    pub kalman: KalmanParams,
+    /// Rate limits applied after smoothing.
+    pub slew: SlewLimits,
+    /// Bridges short input gaps on the output side.
+    pub extrapolation: Option<Extrapolation>,
+    /// Delays packets briefly to put them back in `ts` order.
+    pub jitter: Option<JitterConfig>,
+    /// Append every received message to this JSONL file.
+    pub record: Option<PathBuf>,
+    /// Serve Prometheus metrics on this address.
+    pub metrics: Option<SocketAddr>,
+    /// Shared HMAC secret; when set, unsigned or badly signed packets are dropped.
+    pub secret: Option<String>,
+    /// Shared AES-GCM key; when set, packets that don't decrypt with it are dropped.
+    pub enc_key: Option<String>,
+    pub sinks: SinkConfig,
+    /// Probe every sink at startup and refuse to run if one is unreachable.
+    pub selftest: bool,
+}
+
+/// Outputs to open. With none selected, frames are only logged at debug level.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SinkConfig {
+    /// MAVLink flight controller address.
+    pub mavlink: Option<SocketAddr>,
+    /// geometry_msgs/Twist topic.
+    #[cfg(feature = "ros2")]
+    pub ros2_topic: Option<String>,
+    /// ROS2 node name, `bci_flystick` if unset.
+    #[cfg(feature = "ros2")]
+    pub ros2_node: Option<String>,
+    /// Virtual joystick device name.
+    #[cfg(target_os = "linux")]
+    pub uinput: Option<String>,
+    /// X-Plane address to send `DATA` packets to.
+    pub xplane: Option<SocketAddr>,
+    /// CSV log of emitted frames, written in addition to the outputs above.
+    pub csv: Option<PathBuf>,
+    /// Read-only WebSocket feed of emitted frames, in addition to the outputs above.
+    pub ws: Option<SocketAddr>,
+    /// Extra aircraft addressed by `target`; the outputs above take untargeted frames.
+    pub routes: Vec<Route>,
+}
+
+/// A MAVLink vehicle that receives the frames carrying its `target` id.
+///
+/// The id doubles as the vehicle's MAVLink system id: autopilots ignore
+/// `MANUAL_CONTROL` addressed to another system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Route {
+    pub target: u8,
+    pub mavlink: SocketAddr,
+}
+
+impl Route {
+    /// Opens the MAVLink output for this vehicle.
+    pub fn sink(&self) -> io::Result<MavlinkSink> {
+        Ok(MavlinkSink::new(self.mavlink)?.with_target_system(self.target))
+    }
+}
+
+impl Config {
+    pub fn failsafe_timeout(&self) -> Duration {
+        Duration::from_millis(self.failsafe_ms)
+    }
+
+    pub fn recovery_time(&self) -> Duration {
+        Duration::from_millis(self.recover_ms)
+    }
+
+    /// What to output while there is no usable input.
+    pub fn failsafe_frame(&self) -> ControlFrame {
+        self.failsafe.frame()
+    }
+
+    /// Reads a TOML profile; keys it leaves out keep their defaults.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Config> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| {
+            io::Error::new(e.kind(), format!("cannot read {}: {e}", path.display()))
+        })?;
+        let cfg: Config = toml::from_str(&text).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid config {}: {e}", path.display()),
+            )
+        })?;
+        cfg.validate().map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid config {}: {e}", path.display()),
+            )
+        })?;
+        Ok(cfg)
+    }
+
+    /// Checks the ranges the command-line parsers enforce, for values that
+    /// came from a file.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.failsafe_ms == 0 {
+            return Err("failsafe_ms must be at least 1".into());
+        }
+        self.failsafe.validate()?;
+        if !(64..=65_535).contains(&self.recv_buffer) {
+            return Err(format!(
+                "recv_buffer {} is outside 64..=65535",
+                self.recv_buffer
+            ));
+        }
+        if !(0.1..=1000.0).contains(&self.output_hz) {
+            return Err(format!(
+                "output_hz {} is outside 0.1..=1000",
+                self.output_hz
+            ));
+        }
+        if !(self.alpha > 0.0 && self.alpha <= 1.0) {
+            return Err(format!("alpha {} is outside (0, 1]", self.alpha));
+        }
+        let k = self.kalman;
+        for (name, v) in [
+            ("process_noise", k.process_noise),
+            ("measurement_noise", k.measurement_noise),
+        ] {
+            if !(v.is_finite() && v > 0.0) {
+                return Err(format!("kalman {name} {v} must be a positive number"));
+            }
+        }
+        for (axis, r) in self.calibration.axes() {
+            if !(-1.0 <= r.min && r.min < r.max && r.max <= 1.0) {
+                return Err(format!(
+                    "calibration {axis} {} {} must lie within [-1, 1] with min below max",
+                    r.min, r.max
+                ));
+            }
+        }
+        let curves = [
+            ("yaw", self.curves.yaw),
+            ("pitch", self.curves.pitch),
+            ("roll", self.curves.roll),
+            ("throttle", self.curves.throttle),
+        ];
+        for (axis, AxisCurve { expo, deadzone }) in curves {
+            if !(expo.is_finite() && expo > 0.0) {
+                return Err(format!("{axis} expo {expo} must be a positive number"));
+            }
+            if !(0.0..1.0).contains(&deadzone) {
+                return Err(format!("{axis} deadzone {deadzone} is outside [0, 1)"));
+            }
+        }
+        if let Some(r) = self.alt_range {
+            if !(r.min_m.is_finite() && r.max_m.is_finite() && r.min_m < r.max_m) {
+                return Err(format!(
+                    "alt_range {} {} must be finite with min below max",
+                    r.min_m, r.max_m
+                ));
+            }
+            if !(r.climb_rate.is_finite() && r.climb_rate > 0.0) {
+                return Err(format!(
+                    "alt_range climb_rate {} must be a positive number",
+                    r.climb_rate
+                ));
+            }
+        }
+        if let Some(x) = self.extrapolation {
+            if !(x.max_delta.is_finite() && x.max_delta >= 0.0) {
+                return Err(format!(
+                    "extrapolation max_delta {} must be a non-negative number",
+                    x.max_delta
+                ));
+            }
+        }
+        if let Some(j) = self.jitter {
+            if j.depth == 0 {
+                return Err("jitter depth must be at least 1".into());
+            }
+            if j.delay_ms == 0 || j.delay_ms >= self.failsafe_ms {
+                return Err(format!(
+                    "jitter delay_ms {} must be at least 1 and below failsafe_ms {}",
+                    j.delay_ms, self.failsafe_ms
+                ));
+            }
+        }
+        if self.arm_packets == Some(0) {
+            return Err("arm_packets must be at least 1".into());
+        }
+        if let Some(g) = self.gate {
+            if !(g.threshold > 0.0 && g.threshold <= 1.0) {
+                return Err(format!(
+                    "confidence threshold {} is outside (0, 1]",
+                    g.threshold
+                ));
+            }
+        }
+        for (i, r) in self.sinks.routes.iter().enumerate() {
+            if self.sinks.routes[..i].iter().any(|o| o.target == r.target) {
+                return Err(format!("target {} is routed more than once", r.target));
+            }
+        }
+        for (axis, rate) in [("yaw", self.slew.yaw), ("throttle", self.slew.throttle)] {
+            if let Some(r) = rate {
+                if !(r.is_finite() && r > 0.0) {
+                    return Err(format!("{axis} slew {r} must be a positive number"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bind: SocketAddr::from(([127, 0, 0, 1], 5005)),
+            dual_stack: false,
+            source: None,
+            failsafe_ms: 300,
+            failsafe: FailsafeFrame::default(),
+            recover_ms: 0,
+            output_hz: 50.0,
+            format: Format::Json,
+            recv_buffer: 2048,
+            calibration: Calibration::default(),
+            curves: Curves::default(),
+            invert: Invert::default(),
+            alt_range: None,
+            gate: None,
+            arm_packets: None,
+            filter: FilterKind::default(),
+            alpha: 1.0,
+            kalman: KalmanParams::default(),
+            slew: SlewLimits::default(),
+            extrapolation: None,
+            jitter: None,
+            record: None,
+            metrics: None,
+            secret: None,
+            enc_key: None,
+            sinks: SinkConfig::default(),
+            selftest: false,
+        }
+    }
+}
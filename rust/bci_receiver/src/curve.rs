@@ -0,0 +1,236 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+/// Response shaping for a single normalized axis.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AxisCurve {
+    /// Exponent applied to the magnitude; 1.0 is linear, larger softens the center.
+    pub expo: f32,
+    /// Inputs with a magnitude below this are treated as zero; the rest of the
+    /// range is rescaled so the output still starts at 0 at the threshold.
+    pub deadzone: f32,
+}
+
+impl AxisCurve {
+    pub const LINEAR: AxisCurve = AxisCurve {
+        expo: 1.0,
+        deadzone: 0.0,
+    };
+
+    /// Maps `x` (clamped to `[-1,1]`) through the deadzone and expo curve.
+    pub fn apply(&self, x: f32) -> f32 {
+        let x = x.clamp(-1.0, 1.0);
+        let mag = x.abs();
+        if mag <= self.deadzone {
+            return 0.0;
+        }
+        let mag = (mag - self.deadzone) / (1.0 - self.deadzone);
+        x.signum() * mag.powf(self.expo)
+    }
+}
+
+impl Default for AxisCurve {
+    fn default() -> Self {
+        AxisCurve::LINEAR
+    }
+}
+
+/// Axes whose sign is flipped before any shaping.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Invert {
+    pub yaw: bool,
+    pub altitude: bool,
+    pub pitch: bool,
+    pub roll: bool,
+    pub throttle: bool,
+}
+
+impl Invert {
+    /// Parses a comma-separated axis list such as `yaw,pitch`.
+    pub fn parse_list(s: &str) -> Result<Invert, String> {
+        let mut inv = Invert::default();
+        for axis in s.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+            let flag = match axis {
+                "yaw" => &mut inv.yaw,
+                "altitude" => &mut inv.altitude,
+                "pitch" => &mut inv.pitch,
+                "roll" => &mut inv.roll,
+                "throttle" => &mut inv.throttle,
+                _ => {
+                    return Err(format!(
+                        "unknown axis `{axis}`, expected yaw, altitude, pitch, roll or throttle"
+                    ))
+                }
+            };
+            *flag = true;
+        }
+        Ok(inv)
+    }
+}
+
+/// How the altitude axis drives the height setpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AltMode {
+    /// The axis position is the height: -1 is `min_m`, 1 is `max_m`.
+    #[default]
+    Absolute,
+    /// The axis is a climb rate integrated into the height, see [`ClimbIntegrator`].
+    Rate,
+}
+
+impl FromStr for AltMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "absolute" => Ok(AltMode::Absolute),
+            "rate" => Ok(AltMode::Rate),
+            _ => Err(format!(
+                "unknown altitude mode `{s}`, expected absolute or rate"
+            )),
+        }
+    }
+}
+
+/// Linear map from the normalized altitude axis onto a target height in meters.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AltRange {
+    pub min_m: f32,
+    pub max_m: f32,
+    #[serde(default)]
+    pub mode: AltMode,
+    /// Climb rate in m/s at full deflection, for [`AltMode::Rate`].
+    #[serde(default = "AltRange::default_climb_rate")]
+    pub climb_rate: f32,
+}
+
+impl AltRange {
+    pub const DEFAULT_CLIMB_RATE: f32 = 1.0;
+
+    /// Absolute mapping onto `min_m..=max_m`.
+    pub fn new(min_m: f32, max_m: f32) -> Self {
+        AltRange {
+            min_m,
+            max_m,
+            mode: AltMode::Absolute,
+            climb_rate: AltRange::DEFAULT_CLIMB_RATE,
+        }
+    }
+
+    fn default_climb_rate() -> f32 {
+        AltRange::DEFAULT_CLIMB_RATE
+    }
+
+    /// Maps `x` (clamped to `[-1,1]`) so that -1 gives `min_m` and 1 gives `max_m`.
+    pub fn map(&self, x: f32) -> f32 {
+        let t = (x.clamp(-1.0, 1.0) + 1.0) * 0.5;
+        self.min_m + t * (self.max_m - self.min_m)
+    }
+}
+
+/// Integrates the altitude axis into a height setpoint: holding it up climbs
+/// at up to `climb_rate` m/s, holding it down descends, centered holds.
+///
+/// The time step comes from the packet `ts`. The setpoint starts at `min_m`
+/// and is clamped to the range.
+#[derive(Debug, Clone)]
+pub struct ClimbIntegrator {
+    range: AltRange,
+    target_m: f32,
+    last_ts: Option<f64>,
+}
+
+impl ClimbIntegrator {
+    pub fn new(range: AltRange) -> Self {
+        ClimbIntegrator {
+            range,
+            target_m: range.min_m,
+            last_ts: None,
+        }
+    }
+
+    /// Feeds the axis value `x` taken at `ts` (seconds) and returns the setpoint.
+    pub fn update(&mut self, x: f32, ts: f64) -> f32 {
+        // Out-of-order or repeated timestamps don't integrate.
+        let dt = self.last_ts.map_or(0.0, |last| (ts - last).max(0.0)) as f32;
+        self.last_ts = Some(self.last_ts.map_or(ts, |last| last.max(ts)));
+        let rate = x.clamp(-1.0, 1.0) * self.range.climb_rate;
+        self.target_m = (self.target_m + rate * dt).clamp(self.range.min_m, self.range.max_m);
+        self.target_m
+    }
+
+    /// Drops the time base so an outage isn't integrated. The setpoint is
+    /// kept: it is what the vehicle has been holding meanwhile.
+    pub fn reset(&mut self) {
+        self.last_ts = None;
+    }
+}
+
+/// Curves for the shaped axes. Altitude is passed through unchanged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Curves {
+    pub yaw: AxisCurve,
+    pub pitch: AxisCurve,
+    pub roll: AxisCurve,
+    pub throttle: AxisCurve,
+}
+
+/// Span one user's signal actually covers on an axis, stretched back onto `[-1,1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AxisRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl AxisRange {
+    pub const FULL: AxisRange = AxisRange {
+        min: -1.0,
+        max: 1.0,
+    };
+
+    /// Maps `min` to -1 and `max` to 1 linearly, clamping values outside the span.
+    /// Written around the midpoint so that [`AxisRange::FULL`] is an exact identity.
+    pub fn apply(&self, x: f32) -> f32 {
+        let mid = (self.max + self.min) / 2.0;
+        let half = (self.max - self.min) / 2.0;
+        ((x - mid) / half).clamp(-1.0, 1.0)
+    }
+}
+
+impl Default for AxisRange {
+    fn default() -> Self {
+        AxisRange::FULL
+    }
+}
+
+/// Per-axis input ranges, each stretched linearly onto `[-1,1]` before
+/// inversion and the curves.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Calibration {
+    pub yaw: AxisRange,
+    pub altitude: AxisRange,
+    pub pitch: AxisRange,
+    pub roll: AxisRange,
+    pub throttle: AxisRange,
+}
+
+impl Calibration {
+    /// The axes with their names, in wire order.
+    pub fn axes(&self) -> [(&'static str, AxisRange); 5] {
+        [
+            ("yaw", self.yaw),
+            ("altitude", self.altitude),
+            ("pitch", self.pitch),
+            ("roll", self.roll),
+            ("throttle", self.throttle),
+        ]
+    }
+}
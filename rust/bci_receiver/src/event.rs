@@ -0,0 +1,99 @@
+//! Discrete commands (blinks, clenches, ...) carried next to the analog axes.
+//!
+//! Events travel in the optional `event` field of a message. They are
+//! handled apart from the control frame: a malformed event is counted and
+//! ignored, and the axes of the same packet are processed as usual.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+/// Longest accepted event name.
+const MAX_NAME_LEN: usize = 32;
+
+/// A discrete command from the sender.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BciEvent {
+    /// Reserved: forces the failsafe frame and disarms, whatever else is going on.
+    Estop,
+    /// Switch between flight modes, e.g. on a double blink.
+    ToggleMode,
+    /// Any other well-formed name, left to the registered handlers.
+    Custom(String),
+}
+
+impl FromStr for BciEvent {
+    type Err = String;
+
+    /// Names are lowercase ASCII letters, digits and `_`, starting with a letter.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "estop" => Ok(BciEvent::Estop),
+            "toggle_mode" => Ok(BciEvent::ToggleMode),
+            _ if s.len() <= MAX_NAME_LEN
+                && s.starts_with(|c: char| c.is_ascii_lowercase())
+                && s.chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') =>
+            {
+                Ok(BciEvent::Custom(s.to_owned()))
+            }
+            _ => Err(format!("malformed event `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for BciEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BciEvent::Estop => f.write_str("estop"),
+            BciEvent::ToggleMode => f.write_str("toggle_mode"),
+            BciEvent::Custom(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Receives every event the pipeline accepts, `estop` included.
+pub trait EventHandler: Send {
+    /// `ts` is the timestamp of the packet that carried the event.
+    fn handle(&mut self, event: &BciEvent, ts: f64);
+}
+
+impl<F: FnMut(&BciEvent, f64) + Send> EventHandler for F {
+    fn handle(&mut self, event: &BciEvent, ts: f64) {
+        self(event, ts)
+    }
+}
+
+/// The handlers registered on a pipeline, called in registration order.
+#[derive(Default)]
+pub struct EventHandlers(Vec<Box<dyn EventHandler>>);
+
+impl fmt::Debug for EventHandlers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EventHandlers({})", self.0.len())
+    }
+}
+
+impl EventHandlers {
+    pub fn push(&mut self, handler: impl EventHandler + 'static) {
+        self.0.push(Box::new(handler));
+    }
+
+    pub fn dispatch(&mut self, event: &BciEvent, ts: f64) {
+        for h in &mut self.0 {
+            h.handle(event, ts);
+        }
+    }
+}
+
+/// Deserializes `event` without ever failing the message: a non-string
+/// value is kept as its JSON text, which then doesn't parse as an event.
+pub(crate) fn lenient<'de, D: Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
+    Ok(
+        Option::<serde_json::Value>::deserialize(d)?.map(|v| match v {
+            serde_json::Value::String(s) => s,
+            other => other.to_string(),
+        }),
+    )
+}
@@ -0,0 +1,101 @@
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::ControlFrame;
+
+/// Axis values emitted while the failsafe is tripped or the receiver is disarmed.
+///
+/// Defaults to [`ControlFrame::NEUTRAL`]; airframes whose safe state is not
+/// centered sticks can override individual axes.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FailsafeFrame {
+    pub yaw: f32,
+    pub altitude: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    pub throttle: f32,
+}
+
+impl Default for FailsafeFrame {
+    fn default() -> Self {
+        let n = ControlFrame::NEUTRAL;
+        FailsafeFrame {
+            yaw: n.yaw,
+            altitude: n.altitude,
+            pitch: n.pitch,
+            roll: n.roll,
+            throttle: n.throttle,
+        }
+    }
+}
+
+impl FailsafeFrame {
+    pub fn frame(&self) -> ControlFrame {
+        ControlFrame {
+            yaw: self.yaw,
+            altitude: self.altitude,
+            pitch: self.pitch,
+            roll: self.roll,
+            throttle: self.throttle,
+            ..ControlFrame::NEUTRAL
+        }
+    }
+
+    /// Every axis must be finite and within `[-1,1]`, like a decoded frame.
+    pub fn validate(&self) -> Result<(), String> {
+        for (axis, v) in [
+            ("yaw", self.yaw),
+            ("altitude", self.altitude),
+            ("pitch", self.pitch),
+            ("roll", self.roll),
+            ("throttle", self.throttle),
+        ] {
+            if !(-1.0..=1.0).contains(&v) {
+                return Err(format!("failsafe {axis} {v} is outside [-1, 1]"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tracks how long ago the last valid packet arrived.
+#[derive(Debug)]
+pub struct Failsafe {
+    timeout: Duration,
+    last_valid: Instant,
+    tripped: bool,
+}
+
+impl Failsafe {
+    pub fn new(timeout: Duration, now: Instant) -> Self {
+        Failsafe {
+            timeout,
+            last_valid: now,
+            tripped: false,
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn is_tripped(&self) -> bool {
+        self.tripped
+    }
+
+    /// Records a valid packet. Returns `true` if this clears a tripped failsafe.
+    pub fn feed(&mut self, now: Instant) -> bool {
+        self.last_valid = now;
+        std::mem::replace(&mut self.tripped, false)
+    }
+
+    /// Returns `true` while the link is stale and neutral frames should be emitted.
+    pub fn check(&mut self, now: Instant) -> bool {
+        if now.duration_since(self.last_valid) >= self.timeout {
+            self.tripped = true;
+        }
+        self.tripped
+    }
+}
@@ -0,0 +1,302 @@
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::ControlFrame;
+
+/// Smoothing applied to incoming frames.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterKind {
+    /// Pass frames through unchanged.
+    None,
+    /// [`Smoother`] on every axis.
+    #[default]
+    Ema,
+    /// [`KalmanFilter1D`] on altitude, [`Smoother`] on the other axes.
+    Kalman,
+}
+
+impl FromStr for FilterKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(FilterKind::None),
+            "ema" => Ok(FilterKind::Ema),
+            "kalman" => Ok(FilterKind::Kalman),
+            _ => Err(format!(
+                "unknown filter `{s}`, expected none, ema or kalman"
+            )),
+        }
+    }
+}
+
+/// Per-axis exponential moving average over consecutive frames.
+#[derive(Debug, Clone)]
+pub struct Smoother {
+    alpha: f32,
+    prev: ControlFrame,
+}
+
+impl Smoother {
+    /// `alpha` is the weight of the newest frame; 1.0 disables smoothing.
+    pub fn new(alpha: f32) -> Self {
+        Smoother {
+            alpha: alpha.clamp(0.0, 1.0),
+            prev: ControlFrame::NEUTRAL,
+        }
+    }
+
+    pub fn apply(&mut self, f: &ControlFrame) -> ControlFrame {
+        let a = self.alpha;
+        let ema = |new: f32, prev: f32| a * new + (1.0 - a) * prev;
+        self.prev = ControlFrame {
+            yaw: ema(f.yaw, self.prev.yaw),
+            altitude: ema(f.altitude, self.prev.altitude),
+            pitch: ema(f.pitch, self.prev.pitch),
+            roll: ema(f.roll, self.prev.roll),
+            throttle: ema(f.throttle, self.prev.throttle),
+            ts: f.ts,
+            altitude_m: f.altitude_m,
+            target: f.target,
+            safe: f.safe,
+        };
+        self.prev
+    }
+
+    /// Forgets history so the next frame is blended against neutral.
+    pub fn reset(&mut self) {
+        self.reset_to(&ControlFrame::NEUTRAL);
+    }
+
+    /// Forgets history so the next frame is blended against `f`.
+    pub fn reset_to(&mut self, f: &ControlFrame) {
+        self.prev = *f;
+    }
+}
+
+/// Maximum change per second for the rate-limited axes; `None` leaves an axis free.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlewLimits {
+    pub yaw: Option<f32>,
+    pub throttle: Option<f32>,
+}
+
+/// Caps how fast yaw and throttle may change between frames.
+///
+/// Unlike [`Smoother`] this guarantees a maximum step size: a frame stamped
+/// `dt` after the previous one moves each limited axis by at most `rate * dt`.
+/// Like [`crate::ClimbIntegrator`] the step comes from the packet `ts`, so
+/// frames released together by the jitter buffer still move at the sender's pace.
+#[derive(Debug, Clone)]
+pub struct SlewLimiter {
+    limits: SlewLimits,
+    prev: ControlFrame,
+    last_ts: Option<f64>,
+}
+
+impl SlewLimiter {
+    /// Starts from neutral, so the first frames ramp up from idle as well.
+    pub fn new(limits: SlewLimits) -> Self {
+        SlewLimiter {
+            limits,
+            prev: ControlFrame::NEUTRAL,
+            last_ts: None,
+        }
+    }
+
+    /// The first frame after a reset only sets the time base and stays at
+    /// the held frame.
+    pub fn apply(&mut self, f: &ControlFrame) -> ControlFrame {
+        let dt = self.last_ts.map_or(0.0, |last| (f.ts - last).max(0.0)) as f32;
+        self.last_ts = Some(self.last_ts.map_or(f.ts, |last| last.max(f.ts)));
+        let limit = |rate: Option<f32>, new: f32, prev: f32| match rate {
+            Some(r) => prev + (new - prev).clamp(-r * dt, r * dt),
+            None => new,
+        };
+        self.prev = ControlFrame {
+            yaw: limit(self.limits.yaw, f.yaw, self.prev.yaw),
+            throttle: limit(self.limits.throttle, f.throttle, self.prev.throttle),
+            ..*f
+        };
+        self.prev
+    }
+
+    /// Restarts from neutral.
+    pub fn reset(&mut self) {
+        self.reset_to(&ControlFrame::NEUTRAL);
+    }
+
+    /// Restarts from `f`, e.g. the frame the failsafe has been outputting.
+    pub fn reset_to(&mut self, f: &ControlFrame) {
+        self.prev = *f;
+        self.last_ts = None;
+    }
+}
+
+/// Blends from the failsafe frame back to live input after the link recovers,
+/// so control resumes without a jump.
+#[derive(Debug, Clone)]
+pub struct RecoveryRamp {
+    duration: Duration,
+    from: ControlFrame,
+    started: Option<Instant>,
+}
+
+impl RecoveryRamp {
+    /// A zero `duration` disables the ramp.
+    pub fn new(duration: Duration, from: ControlFrame) -> Self {
+        RecoveryRamp {
+            duration,
+            from,
+            started: None,
+        }
+    }
+
+    /// Begins a ramp from the failsafe frame at `now`.
+    pub fn start(&mut self, now: Instant) {
+        if !self.duration.is_zero() {
+            self.started = Some(now);
+        }
+    }
+
+    pub fn apply(&mut self, f: &ControlFrame, now: Instant) -> ControlFrame {
+        let Some(started) = self.started else {
+            return *f;
+        };
+        let t = now.duration_since(started).as_secs_f32() / self.duration.as_secs_f32();
+        if t >= 1.0 {
+            self.started = None;
+            return *f;
+        }
+        let from = &self.from;
+        let lerp = |live: f32, safe: f32| safe + (live - safe) * t;
+        ControlFrame {
+            yaw: lerp(f.yaw, from.yaw),
+            altitude: lerp(f.altitude, from.altitude),
+            pitch: lerp(f.pitch, from.pitch),
+            roll: lerp(f.roll, from.roll),
+            throttle: lerp(f.throttle, from.throttle),
+            ..*f
+        }
+    }
+}
+
+/// Noise parameters for [`KalmanFilter1D`].
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KalmanParams {
+    /// Variance of the unmodelled acceleration, in units²/s⁴. Higher tracks
+    /// changes faster, lower smooths more.
+    pub process_noise: f32,
+    /// Variance of a single measurement, in units².
+    pub measurement_noise: f32,
+}
+
+impl Default for KalmanParams {
+    fn default() -> Self {
+        KalmanParams {
+            process_noise: 0.5,
+            measurement_noise: 0.05,
+        }
+    }
+}
+
+/// Constant-velocity Kalman filter estimating position and rate of one axis.
+///
+/// The time step comes from the packet `ts`, so irregular sample spacing is
+/// handled exactly rather than assuming a fixed rate.
+#[derive(Debug, Clone)]
+pub struct KalmanFilter1D {
+    q: f64,
+    r: f64,
+    /// Position and velocity estimate.
+    x: [f64; 2],
+    /// Estimate covariance.
+    p: [[f64; 2]; 2],
+    /// Whether `x` holds an estimate, from a measurement or [`KalmanFilter1D::reset_to`].
+    seeded: bool,
+    last_ts: Option<f64>,
+}
+
+impl KalmanFilter1D {
+    pub fn new(params: KalmanParams) -> Self {
+        KalmanFilter1D {
+            q: f64::from(params.process_noise),
+            r: f64::from(params.measurement_noise),
+            x: [0.0; 2],
+            p: [[0.0; 2]; 2],
+            seeded: false,
+            last_ts: None,
+        }
+    }
+
+    /// Current rate estimate in units per second.
+    pub fn velocity(&self) -> f32 {
+        self.x[1] as f32
+    }
+
+    /// Feeds measurement `z` taken at `ts` (seconds) and returns the filtered position.
+    pub fn update(&mut self, z: f32, ts: f64) -> f32 {
+        let z = f64::from(z);
+        if !self.seeded {
+            self.seed(z);
+            self.last_ts = Some(ts);
+            return z as f32;
+        }
+        // Repeated or out-of-order timestamps skip the prediction step, and so
+        // does the first measurement after a reset since no time base is known.
+        let dt = self.last_ts.map_or(0.0, |last| (ts - last).max(0.0));
+        self.last_ts = Some(self.last_ts.map_or(ts, |last| last.max(ts)));
+        if dt > 0.0 {
+            self.predict(dt);
+        }
+
+        let [[p00, p01], [p10, p11]] = self.p;
+        let s = p00 + self.r;
+        let k = [p00 / s, p10 / s];
+        let y = z - self.x[0];
+        self.x = [self.x[0] + k[0] * y, self.x[1] + k[1] * y];
+        self.p = [
+            [(1.0 - k[0]) * p00, (1.0 - k[0]) * p01],
+            [p10 - k[1] * p00, p11 - k[1] * p01],
+        ];
+        self.x[0] as f32
+    }
+
+    fn predict(&mut self, dt: f64) {
+        let [[p00, p01], [p10, p11]] = self.p;
+        self.x[0] += self.x[1] * dt;
+        // F P Fᵀ with F = [[1, dt], [0, 1]], plus white-noise acceleration.
+        let (dt2, dt3, dt4) = (dt * dt, dt * dt * dt, dt * dt * dt * dt);
+        self.p = [
+            [
+                p00 + dt * (p01 + p10) + dt2 * p11 + self.q * dt4 / 4.0,
+                p01 + dt * p11 + self.q * dt3 / 2.0,
+            ],
+            [p10 + dt * p11 + self.q * dt3 / 2.0, p11 + self.q * dt2],
+        ];
+    }
+
+    /// Forgets the estimate; the next measurement re-initializes it.
+    pub fn reset(&mut self) {
+        self.seeded = false;
+        self.last_ts = None;
+    }
+
+    /// Restarts from `position` at rest, e.g. the altitude the failsafe has
+    /// been holding, so the next measurement is blended in rather than jumped to.
+    pub fn reset_to(&mut self, position: f32) {
+        self.seed(f64::from(position));
+        self.last_ts = None;
+    }
+
+    fn seed(&mut self, position: f64) {
+        self.x = [position, 0.0];
+        self.p = [[self.r, 0.0], [0.0, 1.0]];
+        self.seeded = true;
+    }
+}
@@ -0,0 +1,80 @@
+//! Gating of commands the classifier was unsure about.
+
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::ControlFrame;
+
+/// What to do with a frame whose confidence is below the threshold.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GateMode {
+    /// Replace the stick axes with the failsafe frame's.
+    #[default]
+    Zero,
+    /// Scale the stick axes' offset from the failsafe frame by `confidence / threshold`.
+    Attenuate,
+    /// Only count the frame.
+    Pass,
+}
+
+impl FromStr for GateMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zero" => Ok(GateMode::Zero),
+            "attenuate" => Ok(GateMode::Attenuate),
+            "pass" => Ok(GateMode::Pass),
+            _ => Err(format!(
+                "unknown confidence mode `{s}`, expected zero, attenuate or pass"
+            )),
+        }
+    }
+}
+
+/// Confidence threshold and the action taken below it.
+///
+/// Only the stick axes (yaw, pitch, roll, throttle) are gated; altitude is a
+/// target rather than a deflection and is left alone. Messages without a
+/// `confidence` field are never gated.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfidenceGate {
+    pub threshold: f32,
+    #[serde(default)]
+    pub mode: GateMode,
+}
+
+impl ConfidenceGate {
+    /// Returns the frame to use if `confidence` is below the threshold, or
+    /// `None` if the frame is confident enough to pass untouched. Gated axes
+    /// fall back towards `safe`, the configured failsafe frame.
+    pub fn apply(
+        &self,
+        confidence: Option<f32>,
+        f: &ControlFrame,
+        safe: &ControlFrame,
+    ) -> Option<ControlFrame> {
+        let c = confidence?;
+        // Written so that a NaN confidence counts as uncertain.
+        if c >= self.threshold {
+            return None;
+        }
+        let k = match self.mode {
+            GateMode::Zero => 0.0,
+            GateMode::Attenuate if c > 0.0 => c / self.threshold,
+            GateMode::Attenuate => 0.0,
+            GateMode::Pass => return Some(*f),
+        };
+        let toward = |rest: f32, v: f32| rest + (v - rest) * k;
+        Some(ControlFrame {
+            yaw: toward(safe.yaw, f.yaw),
+            pitch: toward(safe.pitch, f.pitch),
+            roll: toward(safe.roll, f.roll),
+            throttle: toward(safe.throttle, f.throttle),
+            ..*f
+        })
+    }
+}
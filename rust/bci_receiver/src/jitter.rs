@@ -0,0 +1,81 @@
+//! Playout buffer that trades a little latency for in-order output on links
+//! with uneven delivery.
+
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+/// Settings for [`JitterBuffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JitterConfig {
+    /// Most packets held at once; a fuller buffer releases its oldest early.
+    pub depth: usize,
+    /// How long each packet is held after arrival.
+    pub delay_ms: u64,
+}
+
+impl Default for JitterConfig {
+    fn default() -> Self {
+        JitterConfig {
+            depth: 3,
+            delay_ms: 40,
+        }
+    }
+}
+
+/// Holds packets for up to `delay_ms` and releases them in `ts` order.
+///
+/// A packet that arrives after a newer one was already released can't be
+/// put back in order; [`JitterBuffer::push`] refuses it as late.
+#[derive(Debug)]
+pub struct JitterBuffer<T> {
+    depth: usize,
+    delay: Duration,
+    /// Sorted by `ts`, oldest first.
+    pending: Vec<(f64, Instant, T)>,
+    released_ts: Option<f64>,
+}
+
+impl<T> JitterBuffer<T> {
+    pub fn new(cfg: JitterConfig) -> Self {
+        JitterBuffer {
+            depth: cfg.depth.max(1),
+            delay: Duration::from_millis(cfg.delay_ms),
+            pending: Vec::new(),
+            released_ts: None,
+        }
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Queues `item` stamped `ts`, handing it back if it is already too late.
+    pub fn push(&mut self, ts: f64, item: T, now: Instant) -> Result<(), T> {
+        if self.released_ts.is_some_and(|last| ts < last) {
+            return Err(item);
+        }
+        // After any equal timestamps, so duplicates keep their arrival order.
+        let at = self.pending.partition_point(|(t, _, _)| *t <= ts);
+        self.pending.insert(at, (ts, now, item));
+        Ok(())
+    }
+
+    /// The oldest packet if it has been held long enough or the buffer is overfull.
+    pub fn pop_due(&mut self, now: Instant) -> Option<T> {
+        let (_, arrived, _) = self.pending.first()?;
+        if self.pending.len() <= self.depth && now.duration_since(*arrived) < self.delay {
+            return None;
+        }
+        let (ts, _, item) = self.pending.remove(0);
+        self.released_ts = Some(ts);
+        Some(item)
+    }
+
+    /// Drops everything held and forgets the release order, e.g. after the link was lost.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.released_ts = None;
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+
+pub mod arm;
+#[cfg(feature = "async")]
+pub mod async_receiver;
+pub mod auth;
+pub mod calibrate;
+pub mod cipher;
+pub mod codec;
+pub mod config;
+pub mod curve;
+pub mod event;
+pub mod failsafe;
+pub mod filter;
+pub mod gate;
+pub mod jitter;
+pub mod metrics;
+pub mod order;
+pub mod output;
+pub mod pipeline;
+pub mod receiver;
+pub mod record;
+pub mod replay;
+pub mod sink;
+pub mod stats;
+
+pub use codec::{DecodeError, Format};
+pub use config::Config;
+pub use curve::{
+    AltMode, AltRange, AxisCurve, AxisRange, Calibration, ClimbIntegrator, Curves, Invert,
+};
+pub use event::BciEvent;
+pub use gate::{ConfidenceGate, GateMode};
+pub use sink::ControlSink;
+pub use stats::Stats;
+
+/// Raw message as sent by the Python controller over UDP.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BciMsg {
+    pub yaw: f32,
+    pub altitude: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pitch: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roll: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+    pub ts: f64,
+    /// Classifier certainty for this prediction, usually in `[0,1]`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    /// Arm (`true`) or disarm (`false`) request, see [`arm::Arming`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arm: Option<bool>,
+    /// Sender sequence number, used to reject reordered and duplicated packets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u32>,
+    /// Message schema version, see [`LATEST_VERSION`]. Absent means 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub v: Option<u32>,
+    /// Aircraft this message addresses, see [`sink::RouterSink`]. Absent means the default output.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<u8>,
+    /// Discrete command such as `"estop"`, see [`BciEvent`].
+    #[serde(
+        default,
+        deserialize_with = "event::lenient",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub event: Option<String>,
+}
+
+/// Newest message schema this receiver understands.
+///
+/// - 1: `throttle` is optional and falls back to `speed` (see [`resolve_throttle`]).
+/// - 2: `throttle` is required and `speed` is ignored.
+pub const LATEST_VERSION: u32 = 2;
+
+/// Control values with every optional field resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ControlFrame {
+    pub yaw: f32,
+    pub altitude: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    pub throttle: f32,
+    pub ts: f64,
+    /// Target height in meters, set when an altitude range is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub altitude_m: Option<f32>,
+    /// Aircraft the frame is for, copied from [`BciMsg::target`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<u8>,
+    /// A failsafe frame (timeout, disarm, estop) rather than live input; the
+    /// output never extrapolates into or out of one.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub safe: bool,
+}
+
+impl ControlFrame {
+    /// Safe output used when the stream is lost: centered sticks, idle throttle.
+    pub const NEUTRAL: ControlFrame = ControlFrame {
+        yaw: 0.0,
+        altitude: 0.0,
+        pitch: 0.0,
+        roll: 0.0,
+        throttle: -1.0,
+        ts: 0.0,
+        altitude_m: None,
+        target: None,
+        safe: true,
+    };
+}
+
+impl From<&BciMsg> for ControlFrame {
+    fn from(m: &BciMsg) -> Self {
+        ControlFrame {
+            yaw: m.yaw,
+            altitude: m.altitude,
+            pitch: m.pitch.unwrap_or(0.0),
+            roll: m.roll.unwrap_or(0.0),
+            throttle: resolve_throttle(m.throttle, m.speed),
+            ts: m.ts,
+            altitude_m: None,
+            target: m.target,
+            safe: false,
+        }
+    }
+}
+
+impl BciMsg {
+    /// Maps the message onto a frame using the rules of its schema version.
+    pub fn to_frame(&self) -> Result<ControlFrame, DecodeError> {
+        match self.v.unwrap_or(1) {
+            1 => Ok(ControlFrame::from(self)),
+            2 => Ok(ControlFrame {
+                throttle: self.throttle.ok_or(DecodeError::Missing("throttle"))?,
+                ..ControlFrame::from(self)
+            }),
+            v => Err(DecodeError::Version(v)),
+        }
+    }
+}
+
+/// Picks the throttle for a message.
+///
+/// An explicit `throttle` in `[-1,1]` wins. Older senders only provide `speed`
+/// in `[0,1]`, which is mapped linearly onto `[-1,1]` (0 → idle, 0.5 → mid,
+/// 1 → full). With neither present the throttle is idle (-1). Out-of-range
+/// speeds are mapped by the same line, and left for the decoder to clamp and
+/// count.
+pub fn resolve_throttle(throttle: Option<f32>, speed: Option<f32>) -> f32 {
+    match (throttle, speed) {
+        (Some(t), _) => t,
+        (None, Some(s)) => s * 2.0 - 1.0,
+        (None, None) => -1.0,
+    }
+}
+
+/// Current wall-clock time as UNIX seconds, the same clock senders use for `ts`.
+pub(crate) fn unix_now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+/// Parses one JSON datagram into a `BciMsg`.
+pub fn parse_msg(bytes: &[u8]) -> Result<BciMsg, DecodeError> {
+    codec::parse_json(bytes)
+}
+
+/// Turns raw datagrams into shaped `ControlFrame`s.
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    pub format: Format,
+    pub calibration: Calibration,
+    pub curves: Curves,
+    pub invert: Invert,
+}
+
+impl Decoder {
+    pub fn new(curves: Curves) -> Self {
+        Decoder {
+            curves,
+            ..Decoder::default()
+        }
+    }
+
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_invert(mut self, invert: Invert) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    pub fn with_calibration(mut self, calibration: Calibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Parses a datagram in the configured wire format.
+    pub fn parse(&self, bytes: &[u8]) -> Result<BciMsg, DecodeError> {
+        self.format.parse(bytes)
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Result<ControlFrame, DecodeError> {
+        self.resolve(&self.parse(bytes)?).map(|s| s.frame)
+    }
+
+    /// Resolves optional fields, validates and clamps the axes, stretches the
+    /// calibrated ranges, flips inverted axes, then applies the curves.
+    ///
+    /// Unknown schema versions and non-finite values reject the packet;
+    /// out-of-range values are clamped to `[-1,1]` and counted in
+    /// [`Sanitized::clamped`].
+    pub fn resolve(&self, m: &BciMsg) -> Result<Sanitized, DecodeError> {
+        let raw = m.to_frame()?;
+        if !raw.ts.is_finite() {
+            return Err(DecodeError::NonFinite("ts"));
+        }
+        let mut clamped = 0;
+        let mut clamp = |name: &'static str, v: f32| {
+            if !v.is_finite() {
+                return Err(DecodeError::NonFinite(name));
+            }
+            let c = v.clamp(-1.0, 1.0);
+            if c != v {
+                clamped += 1;
+            }
+            Ok(c)
+        };
+        let flip = |invert: bool, v: f32| if invert { -v } else { v };
+        let (inv, cal) = (self.invert, self.calibration);
+        let yaw = flip(inv.yaw, cal.yaw.apply(clamp("yaw", raw.yaw)?));
+        let altitude = flip(
+            inv.altitude,
+            cal.altitude.apply(clamp("altitude", raw.altitude)?),
+        );
+        let pitch = flip(inv.pitch, cal.pitch.apply(clamp("pitch", raw.pitch)?));
+        let roll = flip(inv.roll, cal.roll.apply(clamp("roll", raw.roll)?));
+        let throttle = flip(
+            inv.throttle,
+            cal.throttle.apply(clamp("throttle", raw.throttle)?),
+        );
+        Ok(Sanitized {
+            frame: ControlFrame {
+                yaw: self.curves.yaw.apply(yaw),
+                altitude,
+                pitch: self.curves.pitch.apply(pitch),
+                roll: self.curves.roll.apply(roll),
+                throttle: self.curves.throttle.apply(throttle),
+                ts: raw.ts,
+                altitude_m: None,
+                target: raw.target,
+                safe: false,
+            },
+            clamped,
+        })
+    }
+}
+
+/// A validated frame plus how many of its axes had to be clamped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sanitized {
+    pub frame: ControlFrame,
+    pub clamped: u32,
+}
+
+/// Parses one JSON datagram and resolves it into a `ControlFrame` using linear curves.
+pub fn decode_msg(bytes: &[u8]) -> Result<ControlFrame, DecodeError> {
+    Decoder::default().decode(bytes)
+}
@@ -1,27 +1,162 @@
 use std::net::UdpSocket;
-use serde::Deserialize;
-#[derive(Debug, Deserialize)]
-struct BciMsg {
-    yaw: f32,
-    altitude: f32,
-    pitch: Option<f32>,
-    throttle: Option<f32>,
-    speed: Option<f32>,
-    ts: f64,
-}
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bci_receiver::config::SinkConfig;
+use bci_receiver::sink::{
+    ControlSink, CsvSink, LogSink, MavlinkSink, RouterSink, WebSocketSink, XPlaneSink,
+};
+use bci_receiver::{calibrate, replay};
+use clap::Parser;
+use log::info;
+
+mod cli;
+
 fn main() -> std::io::Result<()> {
-    let sock = UdpSocket::bind("127.0.0.1:5005")?;
-    println!("[RUN] UDP 127.0.0.1:5005");
-    let mut buf=[0u8;2048];
-    loop {
-        let (len,_) = sock.recv_from(&mut buf)?;
-        if let Ok(txt) = std::str::from_utf8(&buf[..len]) {
-            if let Ok(m)=serde_json::from_str::<BciMsg>(txt) {
-                let pitch = m.pitch.unwrap_or(0.0);
-                let throttle = m.throttle.unwrap_or_else(|| m.speed.unwrap_or(0.0) * 2.0 - 1.0);
-                println!("Yaw={:+.2} Alt={:+.2} Pitch={:+.2} Thr={:+.2}", m.yaw,m.altitude,pitch,throttle);
-                // TODO: 在这里接入 MAVSDK/ROS2 或模拟器 API
-            }
+    let cli = cli::Cli::parse();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let shutdown = Arc::new(AtomicBool::new(false));
+    {
+        let shutdown = Arc::clone(&shutdown);
+        ctrlc::set_handler(move || shutdown.store(true, Ordering::Relaxed))
+            .map_err(std::io::Error::other)?;
+    }
+
+    let cfg = cli.config()?;
+    if let (Some(secs), Some(path)) = (cli.calibrate, &cli.config) {
+        let sock = bci_receiver::receiver::bind(cfg.bind, cfg.dual_stack)?;
+        let cal = calibrate::record(&sock, &cfg, Duration::from_secs(secs), &shutdown)?;
+        calibrate::save(path, &cal)?;
+        info!("calibration saved to {}", path.display());
+        return Ok(());
+    }
+    let mut sinks = if cli.dry_run {
+        info!("dry run: no outputs are opened, frames are logged at debug level");
+        vec![Box::new(LogSink) as Box<dyn ControlSink + Send>]
+    } else {
+        build_sinks(&cfg)?
+    };
+    if cfg.selftest {
+        probe_sinks(&mut sinks, &cfg.failsafe_frame())?;
+    }
+
+    if let Some(path) = &cli.replay {
+        info!("replay {} at {}x", path.display(), cli.speed);
+        return replay::replay(path, cli.speed, &mut sinks, &cfg, &shutdown);
+    }
+
+    let sock = bci_receiver::receiver::bind(cfg.bind, cfg.dual_stack)?;
+    info!(
+        "UDP {}{}",
+        cfg.bind,
+        if cfg.dual_stack { " (dual-stack)" } else { "" }
+    );
+    run(sock, sinks, &cfg, &shutdown)
+}
+
+#[cfg(feature = "async")]
+fn run(
+    sock: UdpSocket,
+    sinks: Vec<Box<dyn ControlSink + Send>>,
+    cfg: &bci_receiver::Config,
+    shutdown: &AtomicBool,
+) -> std::io::Result<()> {
+    use bci_receiver::async_receiver;
+    use bci_receiver::sink::{AsyncControlSink, Blocking};
+
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    rt.block_on(async {
+        sock.set_nonblocking(true)?;
+        let sock = tokio::net::UdpSocket::from_std(sock)?;
+        let sinks = sinks
+            .into_iter()
+            .map(|s| Box::new(Blocking::new(s)) as Box<dyn AsyncControlSink>)
+            .collect();
+        async_receiver::run(&sock, sinks, cfg, shutdown).await
+    })
+}
+
+#[cfg(not(feature = "async"))]
+fn run(
+    sock: UdpSocket,
+    mut sinks: Vec<Box<dyn ControlSink + Send>>,
+    cfg: &bci_receiver::Config,
+    shutdown: &AtomicBool,
+) -> std::io::Result<()> {
+    bci_receiver::receiver::run(&sock, &mut sinks, cfg, shutdown)
+}
+
+/// Every configured sink, or the frame log if none was, behind a router if
+/// targets are configured, plus the CSV log and WebSocket feed if requested.
+fn build_sinks(cfg: &bci_receiver::Config) -> std::io::Result<Vec<Box<dyn ControlSink + Send>>> {
+    let mut sinks = build_outputs(&cfg.sinks)?;
+    if !cfg.sinks.routes.is_empty() {
+        let mut router = RouterSink::new(sinks);
+        for route in &cfg.sinks.routes {
+            info!("target {} -> MAVLink {}", route.target, route.mavlink);
+            router = router.with_route(route.target, vec![Box::new(route.sink()?)]);
         }
+        sinks = vec![Box::new(router)];
+    }
+    // A CSV file or dashboard feed is not an output in its own right, so it
+    // doesn't replace the frame log.
+    if let Some(path) = &cfg.sinks.csv {
+        info!("CSV log -> {}", path.display());
+        sinks.push(Box::new(CsvSink::create(path)?));
+    }
+    if let Some(addr) = cfg.sinks.ws {
+        let ws = WebSocketSink::bind(addr)?;
+        info!("WebSocket on ws://{}", ws.local_addr());
+        sinks.push(Box::new(ws));
+    }
+    Ok(sinks)
+}
+
+/// The configured vehicle or simulator outputs, or the frame log if there are none.
+fn build_outputs(cfg: &SinkConfig) -> std::io::Result<Vec<Box<dyn ControlSink + Send>>> {
+    let mut sinks: Vec<Box<dyn ControlSink + Send>> = Vec::new();
+    if let Some(target) = cfg.mavlink {
+        info!("MAVLink -> {target}");
+        sinks.push(Box::new(MavlinkSink::new(target)?));
+    }
+    if let Some(target) = cfg.xplane {
+        info!("X-Plane -> {target}");
+        sinks.push(Box::new(XPlaneSink::new(target)?));
+    }
+    #[cfg(feature = "ros2")]
+    if let Some(topic) = &cfg.ros2_topic {
+        let node = cfg.ros2_node.as_deref().unwrap_or("bci_flystick");
+        info!("ROS2 {node} -> {topic}");
+        sinks.push(Box::new(bci_receiver::sink::Ros2Sink::new(node, topic)?));
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(name) = &cfg.uinput {
+        info!("uinput joystick \"{name}\"");
+        sinks.push(Box::new(bci_receiver::sink::UinputSink::new(name)?));
+    }
+    if sinks.is_empty() {
+        sinks.push(Box::new(LogSink));
+    }
+    Ok(sinks)
+}
+
+/// Probes each sink in turn, failing on the first that doesn't respond.
+fn probe_sinks(
+    sinks: &mut [Box<dyn ControlSink + Send>],
+    safe: &bci_receiver::ControlFrame,
+) -> std::io::Result<()> {
+    for sink in sinks {
+        sink.probe(safe).map_err(|e| {
+            std::io::Error::new(
+                e.kind(),
+                format!("selftest: {} sink failed: {e}", sink.name()),
+            )
+        })?;
+        info!("selftest: {} sink OK", sink.name());
     }
+    Ok(())
 }
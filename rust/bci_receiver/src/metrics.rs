@@ -0,0 +1,310 @@
+//! Prometheus text-format endpoint over the same [`Stats`] the
+//! [`Reporter`](crate::stats::Reporter) logs its periodic summary and idle
+//! health line from.
+
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use crate::stats::{LatencyBuckets, Stats, StatsSnapshot};
+use crate::unix_now;
+
+/// A client gets this long to send its request before it is dropped.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BOUNDS: [f64; 11] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+/// Minimal HTTP server answering every request with the current metrics.
+///
+/// Runs on its own thread and only ever reads the shared atomics, so a slow
+/// scraper can't hold up reception. Stopped when dropped.
+#[derive(Debug)]
+pub struct MetricsServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    pub fn start(addr: SocketAddr, stats: Arc<Stats>) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .map_err(|e| io::Error::new(e.kind(), format!("failed to bind metrics {addr}: {e}")))?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = Arc::clone(&stop);
+            thread::Builder::new()
+                .name("bci-metrics".into())
+                .spawn(move || serve(listener, &stats, &stop))?
+        };
+        info!("metrics on http://{addr}/metrics");
+        Ok(MetricsServer {
+            addr,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// The bound address, useful when started on port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wake the blocking accept so the thread sees the flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(t) = self.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+fn serve(listener: TcpListener, stats: &Stats, stop: &AtomicBool) {
+    let mut rate = OutputRate::new(Instant::now());
+    for conn in listener.incoming() {
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        let Ok(mut conn) = conn else { continue };
+        let snap = stats.snapshot();
+        let hz = rate.update(snap.frames_out, Instant::now());
+        let dropped = stats.dropped_frames();
+        let latency = stats.latency_buckets(&LATENCY_BOUNDS);
+        // Errors only affect this one scrape.
+        let body = render(&snap, &dropped, &latency, hz, unix_now());
+        let _ = respond(&mut conn, &body);
+    }
+}
+
+fn respond(conn: &mut TcpStream, body: &str) -> io::Result<()> {
+    conn.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    // Every path gets the metrics, so the request itself only needs draining.
+    let mut req = Vec::new();
+    let mut buf = [0u8; 512];
+    while !req.windows(4).any(|w| w == b"\r\n\r\n") && req.len() < 8192 {
+        match conn.read(&mut buf)? {
+            0 => break,
+            n => req.extend_from_slice(&buf[..n]),
+        }
+    }
+    write!(
+        conn,
+        "HTTP/1.0 200 OK\r\n\
+         Content-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+/// Output frames per second, measured between consecutive scrapes.
+struct OutputRate {
+    at: Instant,
+    frames: u64,
+    hz: f64,
+}
+
+impl OutputRate {
+    fn new(now: Instant) -> Self {
+        OutputRate {
+            at: now,
+            frames: 0,
+            hz: 0.0,
+        }
+    }
+
+    fn update(&mut self, frames: u64, now: Instant) -> f64 {
+        let elapsed = now.duration_since(self.at).as_secs_f64();
+        // Back-to-back scrapes would give a noisy rate; keep the previous one.
+        if elapsed >= 1.0 {
+            self.hz = (frames - self.frames) as f64 / elapsed;
+            self.at = now;
+            self.frames = frames;
+        }
+        self.hz
+    }
+}
+
+fn render(
+    s: &StatsSnapshot,
+    dropped: &[(String, u64)],
+    latency: &LatencyBuckets,
+    output_hz: f64,
+    now: f64,
+) -> String {
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: &[(&str, f64)]| {
+        let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+        for (labels, v) in samples {
+            let _ = writeln!(out, "{name}{labels} {v}");
+        }
+    };
+    let n = |v: u64| v as f64;
+    metric(
+        "bci_packets_received_total",
+        "counter",
+        "Datagrams received, valid or not.",
+        &[("", n(s.packets))],
+    );
+    metric(
+        "bci_parse_errors_total",
+        "counter",
+        "Datagrams that could not be decoded.",
+        &[
+            ("{kind=\"utf8\"}", n(s.utf8_errors)),
+            ("{kind=\"json\"}", n(s.json_errors)),
+            ("{kind=\"binary\"}", n(s.binary_errors)),
+            ("{kind=\"msgpack\"}", n(s.msgpack_errors)),
+        ],
+    );
+    metric(
+        "bci_rejected_packets_total",
+        "counter",
+        "Decoded packets that were dropped.",
+        &[
+            ("{reason=\"auth\"}", n(s.auth_failures)),
+            ("{reason=\"decrypt\"}", n(s.decrypt_failures)),
+            ("{reason=\"source\"}", n(s.foreign)),
+            ("{reason=\"oversized\"}", n(s.oversized)),
+            ("{reason=\"invalid\"}", n(s.invalid)),
+            ("{reason=\"target\"}", n(s.unknown_target)),
+            ("{reason=\"version\"}", n(s.unsupported_version)),
+            ("{reason=\"stale\"}", n(s.stale)),
+            ("{reason=\"late\"}", n(s.late)),
+        ],
+    );
+    metric(
+        "bci_clamped_values_total",
+        "counter",
+        "Axis values clamped into range.",
+        &[("", n(s.clamped))],
+    );
+    metric(
+        "bci_gated_frames_total",
+        "counter",
+        "Frames below the confidence threshold.",
+        &[("", n(s.gated))],
+    );
+    metric(
+        "bci_failsafe_activations_total",
+        "counter",
+        "Times the failsafe switched to neutral output.",
+        &[("", n(s.failsafe_trips))],
+    );
+    metric(
+        "bci_events_total",
+        "counter",
+        "Discrete events received.",
+        &[
+            ("{result=\"dispatched\"}", n(s.events)),
+            ("{result=\"malformed\"}", n(s.malformed_events)),
+        ],
+    );
+    metric(
+        "bci_estops_total",
+        "counter",
+        "Emergency stop events received.",
+        &[("", n(s.estops))],
+    );
+    metric(
+        "bci_output_frames_total",
+        "counter",
+        "Frames sent to the sinks.",
+        &[("", n(s.frames_out))],
+    );
+    let labels: Vec<_> = dropped
+        .iter()
+        .map(|(sink, v)| (format!("{{sink=\"{sink}\"}}"), n(*v)))
+        .collect();
+    let samples: Vec<_> = labels.iter().map(|(l, v)| (l.as_str(), *v)).collect();
+    metric(
+        "bci_sink_dropped_frames_total",
+        "counter",
+        "Frames a sink was too slow to take, replaced by newer ones.",
+        &samples,
+    );
+    metric(
+        "bci_output_rate_hz",
+        "gauge",
+        "Frames sent to the sinks per second since the previous scrape.",
+        &[("", output_hz)],
+    );
+    metric(
+        "bci_failsafe_active",
+        "gauge",
+        "1 while the failsafe overrides the input.",
+        &[("", if s.in_failsafe { 1.0 } else { 0.0 })],
+    );
+    metric(
+        "bci_estop_active",
+        "gauge",
+        "1 while an emergency stop holds the failsafe frame.",
+        &[("", if s.estopped { 1.0 } else { 0.0 })],
+    );
+    if let Some(armed) = s.armed {
+        metric(
+            "bci_armed",
+            "gauge",
+            "1 while armed, 0 while disarmed.",
+            &[("", if armed { 1.0 } else { 0.0 })],
+        );
+    }
+    metric(
+        "bci_skewed_packets_total",
+        "counter",
+        "Packets stamped ahead of the local clock.",
+        &[("", n(s.skewed))],
+    );
+    if let Some(l) = s.latency {
+        metric(
+            "bci_latency_seconds",
+            "gauge",
+            "End-to-end packet latency since startup.",
+            &[
+                ("{stat=\"min\"}", l.min.as_secs_f64()),
+                ("{stat=\"mean\"}", l.mean.as_secs_f64()),
+                ("{stat=\"p99\"}", l.p99.as_secs_f64()),
+                ("{stat=\"max\"}", l.max.as_secs_f64()),
+            ],
+        );
+    }
+    if let Some(last) = s.last_valid {
+        metric(
+            "bci_last_packet_age_seconds",
+            "gauge",
+            "Time since the last accepted packet.",
+            &[("", (now - last.as_secs_f64()).max(0.0))],
+        );
+    }
+    let le: Vec<_> = latency
+        .le
+        .iter()
+        .map(|&(bound, n)| match bound {
+            b if b.is_infinite() => ("{le=\"+Inf\"}".to_string(), n),
+            b => (format!("{{le=\"{b}\"}}"), n),
+        })
+        .collect();
+    let _ = writeln!(
+        out,
+        "# HELP bci_packet_latency_seconds End-to-end packet latency since startup.\n\
+         # TYPE bci_packet_latency_seconds histogram"
+    );
+    for (labels, n) in &le {
+        let _ = writeln!(out, "bci_packet_latency_seconds_bucket{labels} {n}");
+    }
+    let _ = writeln!(out, "bci_packet_latency_seconds_sum {}", latency.sum);
+    let _ = writeln!(out, "bci_packet_latency_seconds_count {}", latency.count);
+    out
+}
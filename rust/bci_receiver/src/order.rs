@@ -0,0 +1,39 @@
+/// Drops duplicated or reordered packets.
+///
+/// Packets carrying `seq` are compared with serial-number arithmetic so the
+/// counter may wrap around `u32::MAX`; packets without it fall back to
+/// requiring a strictly increasing `ts`.
+#[derive(Debug, Default)]
+pub struct OrderGuard {
+    last_seq: Option<u32>,
+    last_ts: Option<f64>,
+}
+
+impl OrderGuard {
+    /// Returns `true` if the packet is newer than everything accepted so far.
+    pub fn accept(&mut self, seq: Option<u32>, ts: f64) -> bool {
+        match seq {
+            Some(seq) => {
+                if let Some(last) = self.last_seq {
+                    if (seq.wrapping_sub(last) as i32) <= 0 {
+                        return false;
+                    }
+                }
+                self.last_seq = Some(seq);
+            }
+            None => {
+                if self.last_ts.is_some_and(|last| ts <= last) {
+                    return false;
+                }
+            }
+        }
+        self.last_ts = Some(ts);
+        true
+    }
+
+    /// Forgets the last accepted packet, e.g. after the link was lost and the
+    /// sender may have restarted its counter.
+    pub fn reset(&mut self) {
+        *self = OrderGuard::default();
+    }
+}
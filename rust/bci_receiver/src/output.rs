@@ -0,0 +1,208 @@
+//! Fixed-rate output stage, decoupled from the (bursty) input rate.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::warn;
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::sink::ControlSink;
+use crate::stats::{SinkId, Stats};
+use crate::ControlFrame;
+
+/// The two most recent frames and when the newer one was published.
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub prev: Option<ControlFrame>,
+    pub latest: ControlFrame,
+    pub published_at: Instant,
+}
+
+/// Most recent frames produced by the input side, read by the output thread.
+#[derive(Debug)]
+pub struct FrameSlot {
+    sample: Mutex<Sample>,
+    closed: AtomicBool,
+}
+
+impl FrameSlot {
+    pub fn new(initial: ControlFrame) -> Self {
+        FrameSlot {
+            sample: Mutex::new(Sample {
+                prev: None,
+                latest: initial,
+                published_at: Instant::now(),
+            }),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    pub fn publish(&self, frame: ControlFrame) {
+        let mut s = self.sample.lock().unwrap_or_else(|e| e.into_inner());
+        *s = Sample {
+            prev: Some(s.latest),
+            latest: frame,
+            published_at: Instant::now(),
+        };
+    }
+
+    pub fn latest(&self) -> ControlFrame {
+        self.sample().latest
+    }
+
+    pub fn sample(&self) -> Sample {
+        *self.sample.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The frame to output at `now`: extrapolated if enabled, else the latest.
+    pub fn output(&self, extrapolation: Option<&Extrapolation>, now: Instant) -> ControlFrame {
+        let sample = self.sample();
+        match extrapolation {
+            Some(x) => x.predict(&sample, now),
+            None => sample.latest,
+        }
+    }
+
+    /// Tells the other side to stop.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+}
+
+/// Linear extrapolation over short input gaps.
+///
+/// Between packets the output continues along the trend of the last two
+/// frames, for at most `horizon_ms` after the newest one; after that it holds
+/// until a new frame or the failsafe takes over. Each axis moves at most
+/// `max_delta` away from the newest frame so a bad trend can't run away.
+///
+/// Frames carrying a height setpoint ([`ControlFrame::altitude_m`]) hold
+/// their altitude instead: `max_delta` has no meaning in meters, and the
+/// normalized axis must keep agreeing with the setpoint sent alongside it.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Extrapolation {
+    pub horizon_ms: u64,
+    pub max_delta: f32,
+}
+
+impl Default for Extrapolation {
+    fn default() -> Self {
+        Extrapolation {
+            horizon_ms: 100,
+            max_delta: 0.2,
+        }
+    }
+}
+
+impl Extrapolation {
+    pub fn predict(&self, s: &Sample, now: Instant) -> ControlFrame {
+        let Some(prev) = s.prev else {
+            return s.latest;
+        };
+        let f = s.latest;
+        // Repeated timestamps give no trend, nor do frames for two different
+        // aircraft. Failsafe frames are held exactly, and a trend from one
+        // would run away from where the live input resumes.
+        let span = f.ts - prev.ts;
+        if span <= 0.0 || f.target != prev.target || f.safe || prev.safe {
+            return f;
+        }
+        let horizon = Duration::from_millis(self.horizon_ms);
+        let ahead = now
+            .duration_since(s.published_at)
+            .min(horizon)
+            .as_secs_f64();
+        let k = (ahead / span) as f32;
+        let m = self.max_delta;
+        let step = |new: f32, old: f32| (new + ((new - old) * k).clamp(-m, m)).clamp(-1.0, 1.0);
+        ControlFrame {
+            yaw: step(f.yaw, prev.yaw),
+            altitude: match f.altitude_m {
+                Some(_) => f.altitude,
+                None => step(f.altitude, prev.altitude),
+            },
+            pitch: step(f.pitch, prev.pitch),
+            roll: step(f.roll, prev.roll),
+            throttle: step(f.throttle, prev.throttle),
+            ..f
+        }
+    }
+}
+
+/// Runs `input` on the current thread while a second thread pushes the latest
+/// published frame to `sink` at `cfg.output_hz`, counting each one in `stats`.
+///
+/// Until the first frame is published the output is the failsafe frame.
+/// Returns once `input` finishes or the sink fails, whichever comes first.
+pub fn drive<S, F>(sink: &mut S, cfg: &Config, stats: &Stats, input: F) -> io::Result<()>
+where
+    S: ControlSink + Send + ?Sized,
+    F: FnOnce(&FrameSlot) -> io::Result<()>,
+{
+    let slot = FrameSlot::new(cfg.failsafe_frame());
+    let period = Duration::from_secs_f64(1.0 / cfg.output_hz);
+    let extrapolation = cfg.extrapolation;
+    let (input_res, output_res) = thread::scope(|s| {
+        let output = thread::Builder::new()
+            .name("bci-output".into())
+            .spawn_scoped(s, || {
+                let res = output_loop(&slot, sink, stats, period, extrapolation.as_ref());
+                slot.close();
+                res
+            })?;
+        let res = input(&slot);
+        slot.close();
+        let out = output
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("output thread panicked")));
+        io::Result::Ok((res, out))
+    })?;
+    input_res.and(output_res)
+}
+
+/// Counts frames `sink` missed, warning each time its total crosses a power of two.
+pub(crate) fn record_dropped(stats: &Stats, id: SinkId, sink: &str, n: u64) {
+    let total = stats.record_dropped(id, n);
+    if (total - n).checked_ilog2() != total.checked_ilog2() {
+        warn!("{sink} sink is falling behind, dropped {total} frames so far");
+    }
+}
+
+fn output_loop<S: ControlSink + ?Sized>(
+    slot: &FrameSlot,
+    sink: &mut S,
+    stats: &Stats,
+    period: Duration,
+    extrapolation: Option<&Extrapolation>,
+) -> io::Result<()> {
+    let id = stats.register_sink(sink.name());
+    let mut next = Instant::now();
+    while !slot.is_closed() {
+        sink.send(&slot.output(extrapolation, Instant::now()))?;
+        stats.record_output();
+        next += period;
+        let now = Instant::now();
+        match next.checked_duration_since(now) {
+            Some(wait) => thread::sleep(wait),
+            // Fell behind (slow sink); skip the missed ticks rather than
+            // catching up with a burst of stale frames.
+            None => {
+                let missed = (now - next).as_nanos() / period.as_nanos().max(1);
+                if missed > 0 {
+                    record_dropped(stats, id, sink.name(), missed as u64);
+                }
+                next = now;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,518 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{debug, info, warn};
+
+use crate::arm::{ArmState, Arming};
+use crate::auth::Authenticator;
+use crate::cipher::Cipher;
+use crate::config::Config;
+use crate::event::{BciEvent, EventHandler, EventHandlers};
+use crate::failsafe::Failsafe;
+use crate::filter::{FilterKind, KalmanFilter1D, RecoveryRamp, SlewLimiter, Smoother};
+use crate::jitter::JitterBuffer;
+use crate::metrics::MetricsServer;
+use crate::order::OrderGuard;
+use crate::record::Recorder;
+use crate::stats::Stats;
+use crate::{
+    unix_now, AltMode, AltRange, BciMsg, ClimbIntegrator, ConfidenceGate, ControlFrame,
+    DecodeError, Decoder,
+};
+
+/// A packet that passed decoding, on its way to [`Pipeline::process`].
+#[derive(Debug)]
+struct Accepted {
+    msg: BciMsg,
+    frame: ControlFrame,
+    event: Option<BciEvent>,
+    /// Unix time the datagram was read, so latency excludes any jitter delay.
+    arrived: f64,
+}
+
+/// Socket-independent processing shared by live reception and replay.
+#[derive(Debug)]
+pub struct Pipeline {
+    source: Option<IpAddr>,
+    decoder: Decoder,
+    cipher: Option<Cipher>,
+    auth: Option<Authenticator>,
+    gate: Option<ConfidenceGate>,
+    arming: Option<Arming>,
+    smoother: Smoother,
+    kalman: Option<KalmanFilter1D>,
+    slew: SlewLimiter,
+    ramp: RecoveryRamp,
+    alt_range: Option<AltRange>,
+    climb: Option<ClimbIntegrator>,
+    failsafe: Failsafe,
+    failsafe_frame: ControlFrame,
+    order: OrderGuard,
+    jitter: Option<JitterBuffer<Accepted>>,
+    recorder: Option<Recorder>,
+    metrics: Option<MetricsServer>,
+    stats: Arc<Stats>,
+    measure_latency: bool,
+    max_datagram: usize,
+    /// Target ids with a route; other targets are dropped.
+    targets: Vec<u8>,
+    /// Target of the last frame through the filters.
+    addressed: Option<u8>,
+    handlers: EventHandlers,
+    /// Set by an `estop` event, cleared by re-arming.
+    estopped: bool,
+    last_arrival: Option<Instant>,
+}
+
+impl Pipeline {
+    pub fn new(cfg: &Config, now: Instant) -> io::Result<Self> {
+        let stats = Arc::<Stats>::default();
+        let metrics = cfg
+            .metrics
+            .map(|addr| MetricsServer::start(addr, Arc::clone(&stats)))
+            .transpose()?;
+        if cfg.arm_packets.is_some() {
+            stats.set_armed(false);
+        }
+        let mut pipeline = Pipeline {
+            source: cfg.source.map(|ip| ip.to_canonical()),
+            decoder: Decoder::new(cfg.curves)
+                .with_format(cfg.format)
+                .with_invert(cfg.invert)
+                .with_calibration(cfg.calibration),
+            cipher: cfg.enc_key.as_ref().map(|k| Cipher::new(k.as_bytes())),
+            auth: cfg
+                .secret
+                .as_ref()
+                .map(|s| Authenticator::new(s.as_bytes())),
+            gate: cfg.gate,
+            arming: cfg.arm_packets.map(Arming::new),
+            smoother: Smoother::new(match cfg.filter {
+                FilterKind::None => 1.0,
+                FilterKind::Ema | FilterKind::Kalman => cfg.alpha,
+            }),
+            kalman: (cfg.filter == FilterKind::Kalman).then(|| KalmanFilter1D::new(cfg.kalman)),
+            slew: SlewLimiter::new(cfg.slew),
+            ramp: RecoveryRamp::new(cfg.recovery_time(), cfg.failsafe_frame()),
+            alt_range: cfg.alt_range,
+            climb: cfg
+                .alt_range
+                .filter(|r| r.mode == AltMode::Rate)
+                .map(ClimbIntegrator::new),
+            failsafe: Failsafe::new(cfg.failsafe_timeout(), now),
+            failsafe_frame: cfg.failsafe_frame(),
+            order: OrderGuard::default(),
+            jitter: cfg.jitter.map(JitterBuffer::new),
+            recorder: cfg.record.as_ref().map(Recorder::create).transpose()?,
+            metrics,
+            stats,
+            measure_latency: true,
+            max_datagram: cfg.recv_buffer,
+            targets: cfg.sinks.routes.iter().map(|r| r.target).collect(),
+            addressed: None,
+            handlers: EventHandlers::default(),
+            estopped: false,
+            last_arrival: None,
+        };
+        // Output starts at the failsafe frame, so the filters do too.
+        pipeline.reset_filters();
+        Ok(pipeline)
+    }
+
+    /// Stops comparing packet `ts` with the wall clock, for input that isn't live.
+    pub fn without_latency(mut self) -> Self {
+        self.measure_latency = false;
+        self
+    }
+
+    /// Calls `handler` with every event accepted from now on.
+    pub fn with_event_handler(mut self, handler: impl EventHandler + 'static) -> Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    pub fn failsafe_timeout(&self) -> Duration {
+        self.failsafe.timeout()
+    }
+
+    /// Receive buffer for [`Pipeline::on_datagram`]: one byte more than the
+    /// largest accepted datagram, so a filled buffer means it was truncated.
+    pub fn recv_buffer(&self) -> Vec<u8> {
+        vec![0; self.max_datagram + 1]
+    }
+
+    /// Longest the receive loop may wait before calling [`Pipeline::poll`]:
+    /// the failsafe timeout, or less so buffered packets go out on time.
+    pub fn poll_interval(&self) -> Duration {
+        let timeout = self.failsafe.timeout();
+        match &self.jitter {
+            Some(j) => (j.delay() / 4).clamp(Duration::from_millis(1), timeout),
+            None => timeout,
+        }
+    }
+
+    pub fn stats(&self) -> &Arc<Stats> {
+        &self.stats
+    }
+
+    /// What sinks are left at when the receiver shuts down.
+    pub fn failsafe_frame(&self) -> ControlFrame {
+        self.failsafe_frame
+    }
+
+    /// Flushes the record file, stops the metrics server and returns the session stats.
+    pub fn finish(self) -> io::Result<Arc<Stats>> {
+        drop(self.metrics);
+        if let Some(rec) = self.recorder {
+            rec.finish()?;
+        }
+        Ok(self.stats)
+    }
+
+    /// Checks a datagram's sender against the configured source. Rejected
+    /// datagrams are counted and must not be passed to [`Pipeline::on_datagram`].
+    pub fn accept_source(&mut self, from: SocketAddr) -> bool {
+        let Some(source) = self.source else {
+            return true;
+        };
+        // A dual-stack socket reports IPv4 senders as v4-mapped IPv6 addresses.
+        if from.ip().to_canonical() == source {
+            return true;
+        }
+        let total = self.stats.record_foreign();
+        if total.is_power_of_two() {
+            warn!("dropped packet from unexpected sender {from} ({total} so far)");
+        }
+        false
+    }
+
+    /// Processes one datagram, returning the frame to emit if it was valid.
+    pub fn on_datagram(&mut self, bytes: &[u8], now: Instant) -> Option<ControlFrame> {
+        let arrived = unix_now();
+        let gap = self.last_arrival.map(|t| now.duration_since(t));
+        self.last_arrival = Some(now);
+        self.stats.record_packet(gap);
+
+        if bytes.len() > self.max_datagram {
+            let total = self.stats.record_oversized();
+            if total.is_power_of_two() {
+                warn!(
+                    "dropped datagram longer than {} bytes, raise recv_buffer if the sender needs more ({total} so far)",
+                    self.max_datagram
+                );
+            }
+            return None;
+        }
+
+        let plaintext;
+        let bytes = match &self.cipher {
+            Some(cipher) => match cipher.open(bytes) {
+                Ok(p) => {
+                    plaintext = p;
+                    &plaintext[..]
+                }
+                Err(e) => {
+                    let total = self.stats.record_decrypt_failure();
+                    if total.is_power_of_two() {
+                        warn!("dropped packet: {e} ({total} so far)");
+                    }
+                    return None;
+                }
+            },
+            None => bytes,
+        };
+        let bytes = match &self.auth {
+            Some(auth) => match auth.verify(bytes, self.decoder.format) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    let total = self.stats.record_auth_failure();
+                    if total.is_power_of_two() {
+                        warn!("dropped packet: {e} ({total} so far)");
+                    }
+                    return None;
+                }
+            },
+            None => bytes,
+        };
+        let msg = match self.decoder.parse(bytes) {
+            Ok(msg) => msg,
+            Err(e) => {
+                let total = match e {
+                    DecodeError::Utf8(_) => self.stats.record_utf8_error(),
+                    DecodeError::Json(_) => self.stats.record_json_error(),
+                    DecodeError::BinaryLength(_) => self.stats.record_binary_error(),
+                    DecodeError::MsgPack(_) => self.stats.record_msgpack_error(),
+                    DecodeError::NonFinite(_) | DecodeError::Missing(_) => {
+                        self.stats.record_invalid()
+                    }
+                    DecodeError::Version(_) => self.stats.record_unsupported_version(),
+                };
+                if total.is_power_of_two() {
+                    warn!("dropped undecodable packet: {e} ({total} of this kind so far)");
+                } else {
+                    debug!("dropped undecodable packet: {e}");
+                }
+                return None;
+            }
+        };
+        if let Some(rec) = &mut self.recorder {
+            rec.record(&msg, arrived);
+        }
+        // Events are read before the axes are validated, so an estop is
+        // honoured even from a packet whose controls are unusable.
+        let event = match msg.event.as_deref().map(str::parse::<BciEvent>) {
+            Some(Ok(BciEvent::Estop)) => return Some(self.estop(msg.ts)),
+            Some(Ok(event)) => Some(event),
+            Some(Err(e)) => {
+                let total = self.stats.record_malformed_event();
+                if total.is_power_of_two() {
+                    warn!("ignored event: {e} ({total} so far)");
+                }
+                None
+            }
+            None => None,
+        };
+        let sanitized = match self.decoder.resolve(&msg) {
+            Ok(s) => s,
+            Err(e) => {
+                let total = match e {
+                    DecodeError::Version(_) => self.stats.record_unsupported_version(),
+                    _ => self.stats.record_invalid(),
+                };
+                if total.is_power_of_two() {
+                    warn!("rejected packet: {e} ({total} of this kind so far)");
+                }
+                return None;
+            }
+        };
+        if let Some(target) = msg.target.filter(|t| !self.targets.contains(t)) {
+            let total = self.stats.record_unknown_target();
+            if total.is_power_of_two() {
+                warn!("dropped packet for unknown target {target} ({total} so far)");
+            }
+            return None;
+        }
+        if sanitized.clamped > 0 {
+            let n = u64::from(sanitized.clamped);
+            let total = self.stats.record_clamped(n);
+            // Warn each time the running total crosses a power of two.
+            if (total - n).checked_ilog2() != total.checked_ilog2() {
+                warn!("clamped out-of-range axis values ({total} so far)");
+            }
+        }
+        let accepted = Accepted {
+            msg,
+            frame: sanitized.frame,
+            event,
+            arrived,
+        };
+        let Some(jitter) = &mut self.jitter else {
+            return self.process(accepted, now);
+        };
+        if let Err(late) = jitter.push(accepted.msg.ts, accepted, now) {
+            let total = self.stats.record_late();
+            if total.is_power_of_two() {
+                warn!(
+                    "dropped packet ts={} that arrived after newer ones were released, consider a longer jitter delay ({total} so far)",
+                    late.msg.ts
+                );
+            }
+        }
+        self.release(now)
+    }
+
+    /// Runs the jitter buffer's due packets through [`Pipeline::process`],
+    /// returning the last frame produced.
+    fn release(&mut self, now: Instant) -> Option<ControlFrame> {
+        let mut out = None;
+        while let Some(p) = self.jitter.as_mut().and_then(|j| j.pop_due(now)) {
+            out = self.process(p, now).or(out);
+        }
+        out
+    }
+
+    /// Ordering, failsafe, arming and filtering for a decoded packet.
+    fn process(&mut self, accepted: Accepted, now: Instant) -> Option<ControlFrame> {
+        let Accepted {
+            msg,
+            frame: decoded,
+            event,
+            arrived,
+        } = accepted;
+        if !self.order.accept(msg.seq, msg.ts) {
+            debug!("dropped stale packet seq={:?} ts={}", msg.seq, msg.ts);
+            self.stats.record_stale();
+            return None;
+        }
+        // Only in-order packets fire events, so a duplicate can't toggle twice.
+        if let Some(event) = event {
+            info!("event {event}");
+            self.stats.record_event();
+            self.handlers.dispatch(&event, msg.ts);
+        }
+        if self.failsafe.feed(now) {
+            info!("link restored, leaving failsafe");
+            self.stats.record_recovery();
+            self.ramp.start(now);
+        }
+        let gated = self
+            .gate
+            .and_then(|g| g.apply(msg.confidence, &decoded, &self.failsafe_frame));
+        if gated.is_some() {
+            debug!("gated frame with confidence {:?}", msg.confidence);
+            self.stats.record_gated();
+        }
+        self.stats.record_valid(arrived);
+        if self.measure_latency {
+            let latency = arrived - msg.ts;
+            if let Some(total) = self.stats.record_latency(latency) {
+                if total.is_power_of_two() {
+                    warn!(
+                        "packet ts is {:.1} ms ahead of the local clock, sender clock is skewed ({total} so far)",
+                        -latency * 1e3
+                    );
+                }
+            }
+        }
+        if let Some(arming) = &mut self.arming {
+            let was = arming.state();
+            let state = arming.update(msg.arm);
+            if state != was {
+                self.stats.set_armed(state == ArmState::Armed);
+                match state {
+                    ArmState::Armed => {
+                        info!("armed");
+                        if self.estopped {
+                            info!("emergency stop released");
+                            self.estopped = false;
+                            self.stats.clear_estop();
+                        }
+                        self.ramp.start(now);
+                    }
+                    ArmState::Disarmed => warn!("disarmed by command"),
+                }
+            }
+            if state == ArmState::Disarmed {
+                // Start from the failsafe frame once armed instead of jumping to the held input.
+                self.reset_filters();
+                return Some(ControlFrame {
+                    ts: msg.ts,
+                    ..self.failsafe_frame
+                });
+            }
+        }
+        if self.estopped {
+            // Only reached without arming: re-arming clears the stop, and
+            // while disarmed the branch above already returned.
+            self.reset_filters();
+            return Some(ControlFrame {
+                ts: msg.ts,
+                ..self.failsafe_frame
+            });
+        }
+        if msg.target != self.addressed {
+            // The filters hold the previous aircraft's controls; the new one
+            // starts from the failsafe frame it has been receiving.
+            info!("addressing {}", target_name(msg.target));
+            self.addressed = msg.target;
+            self.reset_filters();
+            self.ramp.start(now);
+        }
+        let input = gated.as_ref().unwrap_or(&decoded);
+        let mut smoothed = self.smoother.apply(input);
+        if let Some(kalman) = &mut self.kalman {
+            smoothed.altitude = kalman.update(input.altitude, input.ts).clamp(-1.0, 1.0);
+        }
+        let ramped = self.ramp.apply(&smoothed, now);
+        let mut frame = self.slew.apply(&ramped);
+        frame.altitude_m = match &mut self.climb {
+            Some(climb) => Some(climb.update(frame.altitude, frame.ts)),
+            None => self.alt_range.map(|r| r.map(frame.altitude)),
+        };
+        Some(frame)
+    }
+
+    /// Called when no valid datagram arrived; returns a frame released by the
+    /// jitter buffer, or the failsafe frame while the link is stale.
+    ///
+    /// The failsafe frame carries no altitude setpoint, so sinks that support
+    /// one leave the vehicle holding its last target height.
+    pub fn poll(&mut self, now: Instant) -> Option<ControlFrame> {
+        if let Some(frame) = self.release(now) {
+            return Some(frame);
+        }
+        let was_tripped = self.failsafe.is_tripped();
+        if !self.failsafe.check(now) {
+            return None;
+        }
+        if !was_tripped {
+            self.stats.record_failsafe();
+            if let Some(arming) = &mut self.arming {
+                if arming.state() == ArmState::Armed {
+                    warn!("disarmed by failsafe");
+                }
+                arming.disarm();
+                self.stats.set_armed(false);
+            }
+            self.reset_filters();
+            self.order.reset();
+            if let Some(jitter) = &mut self.jitter {
+                jitter.clear();
+            }
+            warn!(
+                "no valid packet for {} ms, sending failsafe controls",
+                self.failsafe.timeout().as_millis()
+            );
+        }
+        Some(self.failsafe_frame)
+    }
+
+    /// Forces the failsafe frame and disarms, returning the frame to emit now.
+    fn estop(&mut self, ts: f64) -> ControlFrame {
+        self.stats.record_estop();
+        if !self.estopped {
+            match &self.arming {
+                Some(_) => warn!(
+                    "emergency stop: sending failsafe controls until the sender releases arm and re-arms"
+                ),
+                None => warn!(
+                    "emergency stop: sending failsafe controls until restart, enable arming to release it from the sender"
+                ),
+            }
+        }
+        self.estopped = true;
+        if let Some(arming) = &mut self.arming {
+            // A sender that keeps asking to be armed must not release the stop.
+            arming.latch();
+            self.stats.set_armed(false);
+        }
+        self.reset_filters();
+        self.stats.record_event();
+        self.handlers.dispatch(&BciEvent::Estop, ts);
+        ControlFrame {
+            ts,
+            ..self.failsafe_frame
+        }
+    }
+
+    /// Restarts the filters from the failsafe frame, which is what was last output.
+    fn reset_filters(&mut self) {
+        self.smoother.reset_to(&self.failsafe_frame);
+        if let Some(kalman) = &mut self.kalman {
+            kalman.reset_to(self.failsafe_frame.altitude);
+        }
+        self.slew.reset_to(&self.failsafe_frame);
+        if let Some(climb) = &mut self.climb {
+            climb.reset();
+        }
+    }
+}
+
+fn target_name(target: Option<u8>) -> String {
+    match target {
+        Some(t) => format!("target {t}"),
+        None => "the default output".into(),
+    }
+}
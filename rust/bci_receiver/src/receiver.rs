@@ -0,0 +1,85 @@
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::info;
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::config::Config;
+use crate::output;
+use crate::pipeline::Pipeline;
+use crate::sink::ControlSink;
+use crate::stats::Reporter;
+
+/// Binds the receive socket. IPv6 addresses are IPv6-only unless
+/// `dual_stack` is set, in which case `[::]` also accepts IPv4 senders
+/// (as v4-mapped addresses) regardless of the OS default.
+pub fn bind(addr: SocketAddr, dual_stack: bool) -> io::Result<UdpSocket> {
+    let sock = Socket::new(Domain::for_address(addr), Type::DGRAM, Some(Protocol::UDP))?;
+    if addr.is_ipv6() {
+        sock.set_only_v6(!dual_stack)?;
+    }
+    sock.bind(&addr.into())
+        .map_err(|e| io::Error::new(e.kind(), format!("failed to bind {addr}: {e}")))?;
+    Ok(sock.into())
+}
+
+/// Receives datagrams from `sock` until `shutdown` is set. The latest decoded
+/// frame is forwarded to `sink` at the configured output rate.
+///
+/// If no valid packet arrives within the failsafe timeout, neutral frames are
+/// sent instead until the stream resumes. On shutdown the sink is closed and
+/// the session totals are printed.
+pub fn run(
+    sock: &UdpSocket,
+    sink: &mut (impl ControlSink + Send + ?Sized),
+    cfg: &Config,
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
+    let mut pipeline = Pipeline::new(cfg, Instant::now())?;
+    sock.set_read_timeout(Some(pipeline.poll_interval()))?;
+    let mut reporter = Reporter::new(Duration::from_secs(1), Instant::now());
+    let mut buf = pipeline.recv_buffer();
+    let stats = Arc::clone(pipeline.stats());
+    output::drive(sink, cfg, &stats, |slot| {
+        while !shutdown.load(Ordering::Relaxed) && !slot.is_closed() {
+            reporter.maybe_report(pipeline.stats(), Instant::now());
+            match sock.recv_from(&mut buf) {
+                Ok((_, from)) if !pipeline.accept_source(from) => {}
+                Ok((len, _)) => {
+                    if let Some(frame) = pipeline.on_datagram(&buf[..len], Instant::now()) {
+                        slot.publish(frame);
+                        continue;
+                    }
+                }
+                // A signal (e.g. Ctrl-C) interrupts the read; the loop condition handles it.
+                Err(e) if is_timeout(&e) || e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+            if let Some(frame) = pipeline.poll(Instant::now()) {
+                slot.publish(frame);
+            }
+        }
+        Ok(())
+    })?;
+    finish(pipeline, sink)
+}
+
+/// Flushes the pipeline, closes the sink and prints the session summary.
+pub(crate) fn finish(pipeline: Pipeline, sink: &mut (impl ControlSink + ?Sized)) -> io::Result<()> {
+    let safe = pipeline.failsafe_frame();
+    let stats = pipeline.finish()?;
+    sink.close(&safe)?;
+    info!("total: {}", stats.summary());
+    Ok(())
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    // Unix reports WouldBlock for an expired read timeout, Windows TimedOut.
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
@@ -0,0 +1,116 @@
+//! Appends received messages to a JSONL file for later replay.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use log::warn;
+use serde::Serialize;
+
+use crate::BciMsg;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+/// Lines buffered in memory before new ones are dropped because the disk can't keep up.
+const QUEUE_DEPTH: usize = 1024;
+
+#[derive(Serialize)]
+struct Record<'a> {
+    recv_ts: f64,
+    #[serde(flatten)]
+    msg: &'a BciMsg,
+}
+
+/// Background JSONL writer. Writing happens on its own thread so a slow disk
+/// never stalls the receive loop; lines are dropped if the queue fills up.
+#[derive(Debug)]
+pub struct Recorder {
+    tx: Option<SyncSender<String>>,
+    writer: Option<JoinHandle<io::Result<()>>>,
+    dropped: u64,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (tx, rx) = mpsc::sync_channel::<String>(QUEUE_DEPTH);
+        let writer = thread::Builder::new()
+            .name("bci-record".into())
+            .spawn(move || write_loop(BufWriter::new(file), rx))?;
+        Ok(Recorder {
+            tx: Some(tx),
+            writer: Some(writer),
+            dropped: 0,
+        })
+    }
+
+    /// Queues `msg` with its arrival wall-clock time (UNIX seconds).
+    pub fn record(&mut self, msg: &BciMsg, recv_ts: f64) {
+        let Some(tx) = &self.tx else { return };
+        let line = match serde_json::to_string(&Record { recv_ts, msg }) {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        match tx.try_send(line) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                self.dropped += 1;
+                if self.dropped.is_power_of_two() {
+                    warn!(
+                        "record file is falling behind, {} lines dropped",
+                        self.dropped
+                    );
+                }
+            }
+            // The writer thread died; surface its error from `finish`.
+            Err(TrySendError::Disconnected(_)) => self.tx = None,
+        }
+    }
+
+    /// Number of lines dropped because the writer could not keep up.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Flushes outstanding lines and closes the file.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.close()
+    }
+
+    fn close(&mut self) -> io::Result<()> {
+        self.tx = None;
+        match self.writer.take() {
+            Some(handle) => handle
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("record writer panicked"))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+fn write_loop(mut out: BufWriter<File>, rx: mpsc::Receiver<String>) -> io::Result<()> {
+    let mut last_flush = Instant::now();
+    loop {
+        match rx.recv_timeout(FLUSH_INTERVAL) {
+            Ok(line) => {
+                out.write_all(line.as_bytes())?;
+                out.write_all(b"\n")?;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if last_flush.elapsed() >= FLUSH_INTERVAL {
+            out.flush()?;
+            last_flush = Instant::now();
+        }
+    }
+    out.flush()
+}
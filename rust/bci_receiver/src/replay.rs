@@ -0,0 +1,113 @@
+//! Feeds a `--record` JSONL file back through the pipeline with its original timing.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+use crate::config::Config;
+use crate::output::{self, FrameSlot};
+use crate::pipeline::Pipeline;
+use crate::receiver::finish;
+use crate::sink::ControlSink;
+use crate::stats::Reporter;
+use crate::Format;
+
+/// Replays `path` into `sink`, spacing lines by their `ts` deltas divided by `speed`.
+/// Stops early once `shutdown` is set.
+///
+/// Each line goes through the same decode path as a live datagram, so gaps in
+/// the recording longer than the failsafe timeout trip the failsafe as well.
+/// A `cfg` that also records is rejected, since it would re-record the replay.
+pub fn replay(
+    path: impl AsRef<Path>,
+    speed: f64,
+    sink: &mut (impl ControlSink + Send + ?Sized),
+    cfg: &Config,
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
+    if cfg.record.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--replay can't be combined with --record",
+        ));
+    }
+    let reader = BufReader::new(File::open(path)?);
+    // Recordings are always plain JSONL, whatever the live wire format,
+    // authentication and encryption were.
+    let cfg = Config {
+        format: Format::Json,
+        secret: None,
+        enc_key: None,
+        ..cfg.clone()
+    };
+    // Recorded timestamps are in the past, so they say nothing about latency.
+    let mut pipeline = Pipeline::new(&cfg, Instant::now())?.without_latency();
+    let mut reporter = Reporter::new(Duration::from_secs(1), Instant::now());
+    let mut clock: Option<(f64, Instant)> = None;
+
+    let stats = Arc::clone(pipeline.stats());
+    output::drive(sink, &cfg, &stats, |slot| {
+        for (lineno, line) in reader.lines().enumerate() {
+            if shutdown.load(Ordering::Relaxed) || slot.is_closed() {
+                break;
+            }
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // Lines that don't parse still go through the pipeline so they are counted.
+            if let Ok(msg) = crate::parse_msg(line.as_bytes()) {
+                let (ts0, start) = *clock.get_or_insert((msg.ts, Instant::now()));
+                let offset = ((msg.ts - ts0) / speed).max(0.0);
+                let due = start + Duration::from_secs_f64(offset);
+                if !wait_until(due, &mut pipeline, slot, shutdown) {
+                    break;
+                }
+            } else {
+                warn!("replay line {} is not a valid message", lineno + 1);
+            }
+            reporter.maybe_report(pipeline.stats(), Instant::now());
+
+            if let Some(frame) = pipeline.on_datagram(line.as_bytes(), Instant::now()) {
+                slot.publish(frame);
+            }
+        }
+        // Give the output thread a couple of ticks to send the last line.
+        if !slot.is_closed() {
+            thread::sleep(Duration::from_secs_f64(2.0 / cfg.output_hz));
+        }
+        Ok(())
+    })?;
+    finish(pipeline, sink)
+}
+
+/// Sleeps until `due`, waking up every failsafe period to let it emit neutral
+/// frames. Returns `false` if interrupted by `shutdown` or a closed output.
+fn wait_until(
+    due: Instant,
+    pipeline: &mut Pipeline,
+    slot: &FrameSlot,
+    shutdown: &AtomicBool,
+) -> bool {
+    loop {
+        let now = Instant::now();
+        if now >= due {
+            return true;
+        }
+        if shutdown.load(Ordering::Relaxed) || slot.is_closed() {
+            return false;
+        }
+        thread::sleep((due - now).min(pipeline.poll_interval()));
+        if Instant::now() < due {
+            if let Some(frame) = pipeline.poll(Instant::now()) {
+                slot.publish(frame);
+            }
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::io;
+
+use log::{debug, log_enabled, Level};
+
+use crate::ControlFrame;
+
+pub mod csv;
+pub mod mavlink;
+#[cfg(feature = "ros2")]
+pub mod ros2;
+pub mod router;
+#[cfg(target_os = "linux")]
+pub mod uinput;
+pub mod ws;
+pub mod xplane;
+
+pub use csv::CsvSink;
+pub use mavlink::MavlinkSink;
+#[cfg(feature = "ros2")]
+pub use ros2::Ros2Sink;
+pub use router::RouterSink;
+#[cfg(target_os = "linux")]
+pub use uinput::UinputSink;
+pub use ws::WebSocketSink;
+pub use xplane::XPlaneSink;
+
+/// Destination for decoded control frames.
+pub trait ControlSink {
+    fn send(&mut self, frame: &ControlFrame) -> io::Result<()>;
+
+    /// Short label used in stats and logs.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Checks that the destination is reachable before any input arrives.
+    /// By default this sends the configured failsafe frame `safe` once.
+    fn probe(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        self.send(safe)
+    }
+
+    /// Called once on shutdown, e.g. to leave the vehicle in the failsafe state `safe`.
+    fn close(&mut self, _safe: &ControlFrame) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<S: ControlSink + ?Sized> ControlSink for Box<S> {
+    fn send(&mut self, frame: &ControlFrame) -> io::Result<()> {
+        (**self).send(frame)
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn probe(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        (**self).probe(safe)
+    }
+
+    fn close(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        (**self).close(safe)
+    }
+}
+
+/// Fans each frame out to every sink. All sinks are tried even if one fails;
+/// the first error is returned.
+impl<S: ControlSink> ControlSink for Vec<S> {
+    fn send(&mut self, frame: &ControlFrame) -> io::Result<()> {
+        let mut res = Ok(());
+        for s in self.iter_mut() {
+            res = res.and(s.send(frame));
+        }
+        res
+    }
+
+    fn name(&self) -> &str {
+        "output"
+    }
+
+    fn probe(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        let mut res = Ok(());
+        for s in self.iter_mut() {
+            res = res.and(s.probe(safe));
+        }
+        res
+    }
+
+    fn close(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        let mut res = Ok(());
+        for s in self.iter_mut() {
+            res = res.and(s.close(safe));
+        }
+        res
+    }
+}
+
+/// Boxed future returned by [`AsyncControlSink`] methods.
+#[cfg(feature = "async")]
+pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
+
+/// Asynchronous counterpart of [`ControlSink`], driven from its own task.
+#[cfg(feature = "async")]
+pub trait AsyncControlSink: Send {
+    fn send<'a>(&'a mut self, frame: &'a ControlFrame) -> BoxFuture<'a, io::Result<()>>;
+
+    /// Short label used in stats and logs.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    fn close<'a>(&'a mut self, _safe: &'a ControlFrame) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: AsyncControlSink + ?Sized> AsyncControlSink for Box<S> {
+    fn send<'a>(&'a mut self, frame: &'a ControlFrame) -> BoxFuture<'a, io::Result<()>> {
+        (**self).send(frame)
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn close<'a>(&'a mut self, safe: &'a ControlFrame) -> BoxFuture<'a, io::Result<()>> {
+        (**self).close(safe)
+    }
+}
+
+/// Runs a blocking [`ControlSink`] on tokio's blocking pool so it can't stall
+/// the runtime.
+#[cfg(feature = "async")]
+#[derive(Debug)]
+pub struct Blocking<S>(Option<S>);
+
+#[cfg(feature = "async")]
+impl<S: ControlSink + Send + 'static> Blocking<S> {
+    pub fn new(sink: S) -> Self {
+        Blocking(Some(sink))
+    }
+
+    async fn call<F>(&mut self, op: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut S) -> io::Result<()> + Send + 'static,
+    {
+        let mut sink = self
+            .0
+            .take()
+            .ok_or_else(|| io::Error::other("sink was lost after a panic"))?;
+        let (sink, res) = tokio::task::spawn_blocking(move || {
+            let res = op(&mut sink);
+            (sink, res)
+        })
+        .await
+        .map_err(io::Error::other)?;
+        self.0 = Some(sink);
+        res
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S: ControlSink + Send + 'static> AsyncControlSink for Blocking<S> {
+    fn send<'a>(&'a mut self, frame: &'a ControlFrame) -> BoxFuture<'a, io::Result<()>> {
+        let frame = *frame;
+        Box::pin(self.call(move |s| s.send(&frame)))
+    }
+
+    fn name(&self) -> &str {
+        self.0.as_ref().map_or("lost", |s| s.name())
+    }
+
+    fn close<'a>(&'a mut self, safe: &'a ControlFrame) -> BoxFuture<'a, io::Result<()>> {
+        let safe = *safe;
+        Box::pin(self.call(move |s| s.close(&safe)))
+    }
+}
+
+/// Logs each frame at debug level (`RUST_LOG=debug`).
+#[derive(Debug, Default)]
+pub struct LogSink;
+
+impl ControlSink for LogSink {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    fn send(&mut self, f: &ControlFrame) -> io::Result<()> {
+        if !log_enabled!(Level::Debug) {
+            return Ok(());
+        }
+        let alt = match f.altitude_m {
+            Some(m) => format!("{m:.1}m"),
+            None => format!("{:+.2}", f.altitude),
+        };
+        debug!(
+            "Yaw={:+.2} Alt={alt} Pitch={:+.2} Roll={:+.2} Thr={:+.2}",
+            f.yaw, f.pitch, f.roll, f.throttle
+        );
+        Ok(())
+    }
+}
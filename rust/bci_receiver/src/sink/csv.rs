@@ -0,0 +1,60 @@
+//! Flat CSV log of emitted frames for loading into pandas or a spreadsheet.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use super::ControlSink;
+use crate::{unix_now, ControlFrame};
+
+const HEADER: &str = "recv_ts,yaw,altitude,pitch,throttle,ts";
+
+/// Writes one row per frame sent. Rust's float formatting ignores the
+/// system locale, so the decimal separator is always `.`.
+///
+/// `recv_ts` is the wall-clock time (UNIX seconds) the row was written, not
+/// when the packet arrived: the output stage repeats the latest frame at
+/// `output_hz`, so several rows can share one `ts`. The sender's `ts` column
+/// identifies the packet each row came from.
+#[derive(Debug)]
+pub struct CsvSink {
+    out: BufWriter<File>,
+}
+
+impl CsvSink {
+    /// Creates (or truncates) `path` and writes the header row.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{HEADER}")?;
+        Ok(CsvSink { out })
+    }
+}
+
+impl ControlSink for CsvSink {
+    fn name(&self) -> &str {
+        "csv"
+    }
+
+    fn send(&mut self, f: &ControlFrame) -> io::Result<()> {
+        writeln!(
+            self.out,
+            "{:.6},{},{},{},{},{:.6}",
+            unix_now(),
+            f.yaw,
+            f.altitude,
+            f.pitch,
+            f.throttle,
+            f.ts
+        )
+    }
+
+    /// Flushes the header instead of writing a row, so the log only holds real output.
+    fn probe(&mut self, _safe: &ControlFrame) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    fn close(&mut self, _safe: &ControlFrame) -> io::Result<()> {
+        self.out.flush()?;
+        self.out.get_ref().sync_all()
+    }
+}
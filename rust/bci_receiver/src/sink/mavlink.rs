@@ -0,0 +1,265 @@
+//! Minimal MAVLink v2 encoder for driving a flight controller (e.g. PX4 SITL).
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use super::ControlSink;
+use crate::ControlFrame;
+
+const STX_V2: u8 = 0xFD;
+const STX_V1: u8 = 0xFE;
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+/// Autopilots send a heartbeat every second; allow for a few to go missing.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+const MSG_HEARTBEAT: u32 = 0;
+const MSG_MANUAL_CONTROL: u32 = 69;
+const MSG_SET_POSITION_TARGET_LOCAL_NED: u32 = 84;
+const CRC_EXTRA_HEARTBEAT: u8 = 50;
+const CRC_EXTRA_MANUAL_CONTROL: u8 = 243;
+const CRC_EXTRA_SET_POSITION_TARGET_LOCAL_NED: u8 = 143;
+
+const MAV_TYPE_GCS: u8 = 6;
+const MAV_AUTOPILOT_INVALID: u8 = 8;
+const MAV_STATE_ACTIVE: u8 = 4;
+const MAV_FRAME_LOCAL_NED: u8 = 1;
+const MAV_COMP_ID_AUTOPILOT1: u8 = 1;
+/// POSITION_TARGET_TYPEMASK with every bit set except `Z_IGNORE`, so only the
+/// altitude is commanded.
+const TYPE_MASK_Z_ONLY: u16 = 0x0DFB;
+
+/// Sends each frame as a `MANUAL_CONTROL` message over UDP.
+///
+/// Yaw maps to the `r` (yaw-rate) channel, throttle to the `z` (thrust)
+/// channel, pitch to `x` and roll to `y`. A GCS heartbeat is emitted once per second so the autopilot
+/// accepts the stream as coming from a live ground station.
+///
+/// Frames with [`ControlFrame::altitude_m`] set additionally send a
+/// `SET_POSITION_TARGET_LOCAL_NED` with only the height (above the local
+/// origin) enabled.
+#[derive(Debug)]
+pub struct MavlinkSink {
+    sock: UdpSocket,
+    target: SocketAddr,
+    seq: u8,
+    system_id: u8,
+    component_id: u8,
+    target_system: u8,
+    started: Instant,
+    last_heartbeat: Option<Instant>,
+}
+
+impl MavlinkSink {
+    pub fn new(target: impl ToSocketAddrs) -> io::Result<Self> {
+        let target = target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no MAVLink target"))?;
+        let local: SocketAddr = if target.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
+        };
+        Ok(MavlinkSink {
+            sock: UdpSocket::bind(local)?,
+            target,
+            seq: 0,
+            system_id: 255,
+            component_id: 190,
+            target_system: 1,
+            started: Instant::now(),
+            last_heartbeat: None,
+        })
+    }
+
+    /// Overrides the system id of the vehicle being commanded (default 1).
+    pub fn with_target_system(mut self, id: u8) -> Self {
+        self.target_system = id;
+        self
+    }
+
+    fn send_msg(&mut self, msg_id: u32, crc_extra: u8, payload: &[u8]) -> io::Result<()> {
+        let pkt = encode_v2(
+            self.seq,
+            self.system_id,
+            self.component_id,
+            msg_id,
+            crc_extra,
+            payload,
+        );
+        self.seq = self.seq.wrapping_add(1);
+        self.sock.send_to(&pkt, self.target).map(|_| ())
+    }
+
+    fn heartbeat(&mut self) -> io::Result<()> {
+        let mut p = [0u8; 9];
+        // custom_mode (u32) stays 0
+        p[4] = MAV_TYPE_GCS;
+        p[5] = MAV_AUTOPILOT_INVALID;
+        p[7] = MAV_STATE_ACTIVE;
+        p[8] = 3; // mavlink_version
+        self.send_msg(MSG_HEARTBEAT, CRC_EXTRA_HEARTBEAT, &p)
+    }
+
+    /// Waits for a heartbeat sent back from the target's address.
+    fn await_heartbeat(&self) -> io::Result<()> {
+        let deadline = Instant::now() + PROBE_TIMEOUT;
+        let mut buf = [0u8; 280];
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "no MAVLink heartbeat from {} within {} s",
+                        self.target,
+                        PROBE_TIMEOUT.as_secs()
+                    ),
+                ));
+            }
+            self.sock.set_read_timeout(Some(left))?;
+            match self.sock.recv_from(&mut buf) {
+                Ok((len, from)) if from.ip() == self.target.ip() => {
+                    if msg_id(&buf[..len]) == Some(MSG_HEARTBEAT) {
+                        return Ok(());
+                    }
+                }
+                Ok(_) => {}
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock
+                            | io::ErrorKind::TimedOut
+                            | io::ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl ControlSink for MavlinkSink {
+    fn name(&self) -> &str {
+        "mavlink"
+    }
+
+    fn send(&mut self, f: &ControlFrame) -> io::Result<()> {
+        let now = Instant::now();
+        let due = match self.last_heartbeat {
+            Some(t) => now.duration_since(t) >= HEARTBEAT_INTERVAL,
+            None => true,
+        };
+        if due {
+            self.heartbeat()?;
+            self.last_heartbeat = Some(now);
+        }
+        let payload = manual_control_payload(f, self.target_system);
+        self.send_msg(MSG_MANUAL_CONTROL, CRC_EXTRA_MANUAL_CONTROL, &payload)?;
+        if let Some(alt) = f.altitude_m {
+            let boot_ms = now.duration_since(self.started).as_millis() as u32;
+            let payload = altitude_target_payload(alt, boot_ms, self.target_system);
+            self.send_msg(
+                MSG_SET_POSITION_TARGET_LOCAL_NED,
+                CRC_EXTRA_SET_POSITION_TARGET_LOCAL_NED,
+                &payload,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Sends the failsafe frame, then waits for the autopilot's heartbeat.
+    fn probe(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        self.send(safe)?;
+        let res = self.await_heartbeat();
+        self.sock.set_read_timeout(None)?;
+        res
+    }
+
+    /// Leaves the sticks at the failsafe frame rather than latching the last command.
+    /// No altitude setpoint is sent, so the vehicle keeps its last target height.
+    fn close(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        self.send(safe)
+    }
+}
+
+fn scale(v: f32) -> i16 {
+    (v.clamp(-1.0, 1.0) * 1000.0).round() as i16
+}
+
+fn manual_control_payload(f: &ControlFrame, target_system: u8) -> [u8; 11] {
+    // Fields are ordered by size as required by the MAVLink wire format.
+    let x = scale(f.pitch);
+    let y = scale(f.roll);
+    let z = ((f.throttle.clamp(-1.0, 1.0) + 1.0) * 500.0).round() as i16;
+    let r = scale(f.yaw);
+    let mut p = [0u8; 11];
+    p[0..2].copy_from_slice(&x.to_le_bytes());
+    p[2..4].copy_from_slice(&y.to_le_bytes());
+    p[4..6].copy_from_slice(&z.to_le_bytes());
+    p[6..8].copy_from_slice(&r.to_le_bytes());
+    // buttons (u16) stay 0
+    p[10] = target_system;
+    p
+}
+
+fn altitude_target_payload(alt_m: f32, time_boot_ms: u32, target_system: u8) -> [u8; 53] {
+    let mut p = [0u8; 53];
+    p[0..4].copy_from_slice(&time_boot_ms.to_le_bytes());
+    // x, y at 4..12 are ignored by the type mask; NED z points down.
+    p[12..16].copy_from_slice(&(-alt_m).to_le_bytes());
+    // velocities, accelerations, yaw and yaw rate (16..48) are ignored
+    p[48..50].copy_from_slice(&TYPE_MASK_Z_ONLY.to_le_bytes());
+    p[50] = target_system;
+    p[51] = MAV_COMP_ID_AUTOPILOT1;
+    p[52] = MAV_FRAME_LOCAL_NED;
+    p
+}
+
+/// Message id of a MAVLink v1 or v2 packet, if `pkt` looks like one.
+fn msg_id(pkt: &[u8]) -> Option<u32> {
+    match pkt {
+        [STX_V1, _, _, _, _, id, ..] => Some(u32::from(*id)),
+        [STX_V2, _, _, _, _, _, _, a, b, c, ..] => Some(u32::from_le_bytes([*a, *b, *c, 0])),
+        _ => None,
+    }
+}
+
+fn encode_v2(
+    seq: u8,
+    system_id: u8,
+    component_id: u8,
+    msg_id: u32,
+    crc_extra: u8,
+    payload: &[u8],
+) -> Vec<u8> {
+    // MAVLink 2 truncates trailing zero bytes, keeping at least one.
+    let len = payload.iter().rposition(|&b| b != 0).map_or(1, |i| i + 1);
+    let payload = &payload[..len];
+
+    let mut pkt = Vec::with_capacity(12 + len);
+    pkt.push(STX_V2);
+    pkt.push(len as u8);
+    pkt.push(0); // incompat_flags
+    pkt.push(0); // compat_flags
+    pkt.push(seq);
+    pkt.push(system_id);
+    pkt.push(component_id);
+    pkt.extend_from_slice(&msg_id.to_le_bytes()[..3]);
+    pkt.extend_from_slice(payload);
+
+    let mut crc = pkt[1..]
+        .iter()
+        .fold(0xFFFF, |crc, &b| crc_accumulate(b, crc));
+    crc = crc_accumulate(crc_extra, crc);
+    pkt.extend_from_slice(&crc.to_le_bytes());
+    pkt
+}
+
+/// CRC-16/MCRF4XX step used by MAVLink.
+fn crc_accumulate(b: u8, crc: u16) -> u16 {
+    let mut tmp = b ^ (crc & 0xFF) as u8;
+    tmp ^= tmp << 4;
+    let tmp = tmp as u16;
+    (crc >> 8) ^ (tmp << 8) ^ (tmp << 3) ^ (tmp >> 4)
+}
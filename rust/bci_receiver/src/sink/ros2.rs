@@ -0,0 +1,63 @@
+//! Publishes frames as `geometry_msgs/msg/Twist` on a ROS2 topic.
+
+use std::io;
+
+use r2r::geometry_msgs::msg::{Twist, Vector3};
+use r2r::{Context, Node, Publisher, QosProfile};
+
+use super::ControlSink;
+use crate::ControlFrame;
+
+/// Maps yaw to `angular.z`, roll to `angular.x`, pitch to `linear.x` and
+/// throttle to `linear.z`.
+pub struct Ros2Sink {
+    // Kept alive for as long as the publisher is in use.
+    _node: Node,
+    publisher: Publisher<Twist>,
+}
+
+impl Ros2Sink {
+    pub fn new(node_name: &str, topic: &str) -> io::Result<Self> {
+        let ctx = Context::create().map_err(io::Error::other)?;
+        let mut node = Node::create(ctx, node_name, "").map_err(io::Error::other)?;
+        let publisher = node
+            .create_publisher::<Twist>(topic, QosProfile::default())
+            .map_err(io::Error::other)?;
+        Ok(Ros2Sink {
+            _node: node,
+            publisher,
+        })
+    }
+}
+
+fn twist(f: &ControlFrame) -> Twist {
+    Twist {
+        linear: Vector3 {
+            x: f.pitch.into(),
+            y: 0.0,
+            z: f.throttle.into(),
+        },
+        angular: Vector3 {
+            x: f.roll.into(),
+            y: 0.0,
+            z: f.yaw.into(),
+        },
+    }
+}
+
+impl ControlSink for Ros2Sink {
+    fn name(&self) -> &str {
+        "ros2"
+    }
+
+    fn send(&mut self, f: &ControlFrame) -> io::Result<()> {
+        self.publisher.publish(&twist(f)).map_err(io::Error::other)
+    }
+
+    /// Publishes the failsafe twist so subscribers don't keep acting on the last command.
+    fn close(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        self.publisher
+            .publish(&twist(safe))
+            .map_err(io::Error::other)
+    }
+}
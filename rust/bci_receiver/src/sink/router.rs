@@ -0,0 +1,70 @@
+//! Splits one BCI stream across several aircraft by the frames' `target` id.
+
+use std::io;
+
+use super::ControlSink;
+use crate::ControlFrame;
+
+/// Sends each frame to the sink for its [`ControlFrame::target`], or to the
+/// default sink when it has none.
+///
+/// One aircraft is flown at a time. The others are sent nothing, so each
+/// keeps whatever its autopilot does when its ground station goes quiet.
+/// Failsafe frames ([`ControlFrame::safe`]) are the exception and go to every
+/// sink: without live input no aircraft is being flown.
+#[derive(Debug)]
+pub struct RouterSink<S> {
+    default: S,
+    routes: Vec<(u8, S)>,
+}
+
+impl<S: ControlSink> RouterSink<S> {
+    pub fn new(default: S) -> Self {
+        RouterSink {
+            default,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Sends frames addressed to `target` to `sink`. A later route for the
+    /// same target replaces the earlier one.
+    pub fn with_route(mut self, target: u8, sink: S) -> Self {
+        self.routes.retain(|(t, _)| *t != target);
+        self.routes.push((target, sink));
+        self
+    }
+
+    fn each(&mut self, mut f: impl FnMut(Option<u8>, &mut S) -> io::Result<()>) -> io::Result<()> {
+        let mut res = f(None, &mut self.default);
+        for (target, sink) in &mut self.routes {
+            res = res.and(f(Some(*target), sink));
+        }
+        res
+    }
+}
+
+/// Like the `Vec` fan-out, every sink is tried even if one fails and the
+/// first error is returned.
+impl<S: ControlSink> ControlSink for RouterSink<S> {
+    fn send(&mut self, frame: &ControlFrame) -> io::Result<()> {
+        self.each(|target, sink| {
+            if frame.safe || target == frame.target {
+                sink.send(frame)
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    fn name(&self) -> &str {
+        "router"
+    }
+
+    fn probe(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        self.each(|_, sink| sink.probe(safe))
+    }
+
+    fn close(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        self.each(|_, sink| sink.close(safe))
+    }
+}
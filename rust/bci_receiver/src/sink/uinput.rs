@@ -0,0 +1,152 @@
+//! Virtual joystick on Linux via `/dev/uinput`.
+//!
+//! Axis layout matches `python/feed_uinput.py` so existing sim bindings keep
+//! working: X = altitude, Y = throttle, Z = pitch, RX = yaw, plus RY = roll,
+//! all 0..=65535.
+
+use std::ffi::c_ulong;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+
+use super::ControlSink;
+use crate::ControlFrame;
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_ABS: u16 = 0x03;
+const SYN_REPORT: u16 = 0;
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_Z: u16 = 0x02;
+const ABS_RX: u16 = 0x03;
+const ABS_RY: u16 = 0x04;
+// A trigger button makes udev classify the device as a joystick.
+const BTN_TRIGGER: u16 = 0x120;
+const BUS_VIRTUAL: u16 = 0x06;
+
+const AXIS_MAX: i32 = 65535;
+
+const fn iow(nr: c_ulong, size: usize) -> c_ulong {
+    (1 << 30) | ((size as c_ulong) << 16) | ((b'U' as c_ulong) << 8) | nr
+}
+
+const UI_DEV_CREATE: c_ulong = (b'U' as c_ulong) << 8 | 1;
+const UI_DEV_DESTROY: c_ulong = (b'U' as c_ulong) << 8 | 2;
+const UI_DEV_SETUP: c_ulong = iow(3, std::mem::size_of::<libc::uinput_setup>());
+const UI_ABS_SETUP: c_ulong = iow(4, std::mem::size_of::<libc::uinput_abs_setup>());
+const UI_SET_EVBIT: c_ulong = iow(100, std::mem::size_of::<libc::c_int>());
+const UI_SET_KEYBIT: c_ulong = iow(101, std::mem::size_of::<libc::c_int>());
+const UI_SET_ABSBIT: c_ulong = iow(103, std::mem::size_of::<libc::c_int>());
+
+/// Writes frames as absolute axes of a virtual gamepad.
+#[derive(Debug)]
+pub struct UinputSink {
+    dev: File,
+}
+
+impl UinputSink {
+    /// Creates the virtual device. Fails up front with a descriptive error if
+    /// `/dev/uinput` is missing or not writable.
+    pub fn new(name: &str) -> io::Result<Self> {
+        let dev = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/uinput")
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::PermissionDenied => io::Error::new(
+                    e.kind(),
+                    "cannot open /dev/uinput: permission denied \
+                     (run as root or grant your user access, e.g. via a udev rule for the `input` group)",
+                ),
+                io::ErrorKind::NotFound => io::Error::new(
+                    e.kind(),
+                    "/dev/uinput not found (is the uinput kernel module loaded? try `modprobe uinput`)",
+                ),
+                _ => e,
+            })?;
+        let sink = UinputSink { dev };
+        sink.setup(name)?;
+        Ok(sink)
+    }
+
+    fn ioctl(&self, request: c_ulong, arg: usize) -> io::Result<()> {
+        // SAFETY: `request` is one of the uinput ioctls above and `arg` is either an
+        // integer or a pointer to the matching, live setup struct.
+        let rc = unsafe { libc::ioctl(self.dev.as_raw_fd(), request as _, arg) };
+        if rc < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn setup(&self, name: &str) -> io::Result<()> {
+        self.ioctl(UI_SET_EVBIT, EV_KEY.into())?;
+        self.ioctl(UI_SET_KEYBIT, BTN_TRIGGER.into())?;
+        self.ioctl(UI_SET_EVBIT, EV_ABS.into())?;
+        for code in [ABS_X, ABS_Y, ABS_Z, ABS_RX, ABS_RY] {
+            self.ioctl(UI_SET_ABSBIT, code.into())?;
+            // SAFETY: plain C struct, all-zero is a valid value.
+            let mut abs: libc::uinput_abs_setup = unsafe { std::mem::zeroed() };
+            abs.code = code;
+            abs.absinfo.minimum = 0;
+            abs.absinfo.maximum = AXIS_MAX;
+            abs.absinfo.value = AXIS_MAX / 2;
+            self.ioctl(UI_ABS_SETUP, &abs as *const _ as usize)?;
+        }
+
+        // SAFETY: plain C struct, all-zero is a valid value.
+        let mut setup: libc::uinput_setup = unsafe { std::mem::zeroed() };
+        setup.id.bustype = BUS_VIRTUAL;
+        setup.id.vendor = 0x1209; // pid.codes open-source vendor id
+        setup.id.product = 0xB0C1;
+        setup.id.version = 1;
+        for (dst, src) in setup.name.iter_mut().zip(name.bytes().take(79)) {
+            *dst = src as libc::c_char;
+        }
+        self.ioctl(UI_DEV_SETUP, &setup as *const _ as usize)?;
+        self.ioctl(UI_DEV_CREATE, 0)
+    }
+
+    fn emit(&mut self, kind: u16, code: u16, value: i32) -> io::Result<()> {
+        // SAFETY: plain C struct, all-zero is a valid value (the kernel fills in the time).
+        let mut ev: libc::input_event = unsafe { std::mem::zeroed() };
+        ev.type_ = kind;
+        ev.code = code;
+        ev.value = value;
+        // SAFETY: `input_event` is a POD struct; we only view its bytes.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                &ev as *const _ as *const u8,
+                std::mem::size_of::<libc::input_event>(),
+            )
+        };
+        self.dev.write_all(bytes)
+    }
+}
+
+fn scale(v: f32) -> i32 {
+    ((v.clamp(-1.0, 1.0) + 1.0) * 0.5 * AXIS_MAX as f32).round() as i32
+}
+
+impl ControlSink for UinputSink {
+    fn name(&self) -> &str {
+        "uinput"
+    }
+
+    fn send(&mut self, f: &ControlFrame) -> io::Result<()> {
+        self.emit(EV_ABS, ABS_X, scale(f.altitude))?;
+        self.emit(EV_ABS, ABS_Y, scale(f.throttle))?;
+        self.emit(EV_ABS, ABS_Z, scale(f.pitch))?;
+        self.emit(EV_ABS, ABS_RX, scale(f.yaw))?;
+        self.emit(EV_ABS, ABS_RY, scale(f.roll))?;
+        self.emit(EV_SYN, SYN_REPORT, 0)
+    }
+
+    fn close(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        self.send(safe)?;
+        self.ioctl(UI_DEV_DESTROY, 0)
+    }
+}
@@ -0,0 +1,231 @@
+//! Read-only WebSocket tap broadcasting emitted frames as JSON, for live dashboards.
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::debug;
+use sha1::{Digest, Sha1};
+
+use super::ControlSink;
+use crate::ControlFrame;
+
+/// A client gets this long to complete the handshake before it is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+const OP_TEXT: u8 = 0x1;
+const OP_CLOSE: u8 = 0x8;
+
+/// Sends every frame as a JSON text message to all connected WebSocket clients.
+///
+/// Each handshake runs on its own thread, so a client that stalls in it
+/// can't hold up the others, and client sockets are non-blocking:
+/// a client that can't keep up is disconnected instead of stalling the
+/// output. Anything clients send is ignored. Stopped when dropped.
+#[derive(Debug)]
+pub struct WebSocketSink {
+    addr: SocketAddr,
+    clients: Arc<Mutex<Vec<TcpStream>>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WebSocketSink {
+    pub fn bind(addr: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).map_err(|e| {
+            io::Error::new(e.kind(), format!("failed to bind WebSocket {addr}: {e}"))
+        })?;
+        let addr = listener.local_addr()?;
+        let clients = Arc::default();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let clients = Arc::clone(&clients);
+            let stop = Arc::clone(&stop);
+            thread::Builder::new()
+                .name("bci-ws".into())
+                .spawn(move || accept(listener, clients, &stop))?
+        };
+        Ok(WebSocketSink {
+            addr,
+            clients,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// The bound address, useful when started on port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Writes `msg` to every client, dropping those whose write fails or would block.
+    fn broadcast(&self, msg: &[u8]) {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        clients.retain_mut(|c| match c.write(msg) {
+            Ok(n) if n == msg.len() => true,
+            // A partial frame can't be resumed later, so the stream is unusable.
+            res => {
+                debug!("WebSocket client {:?} dropped: {res:?}", c.peer_addr().ok());
+                false
+            }
+        });
+    }
+}
+
+impl ControlSink for WebSocketSink {
+    fn name(&self) -> &str {
+        "ws"
+    }
+
+    fn send(&mut self, f: &ControlFrame) -> io::Result<()> {
+        let json = serde_json::to_vec(f).map_err(io::Error::other)?;
+        self.broadcast(&ws_frame(OP_TEXT, &json));
+        Ok(())
+    }
+
+    /// Nothing to check: with no clients connected, frames simply go nowhere.
+    fn probe(&mut self, _safe: &ControlFrame) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn close(&mut self, _safe: &ControlFrame) -> io::Result<()> {
+        self.broadcast(&ws_frame(OP_CLOSE, &[]));
+        self.clients
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        Ok(())
+    }
+}
+
+impl Drop for WebSocketSink {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wake the blocking accept so the thread sees the flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(t) = self.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+fn accept(listener: TcpListener, clients: Arc<Mutex<Vec<TcpStream>>>, stop: &AtomicBool) {
+    for conn in listener.incoming() {
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        let Ok(conn) = conn else { continue };
+        let clients = Arc::clone(&clients);
+        let spawned = thread::Builder::new()
+            .name("bci-ws-handshake".into())
+            .spawn(move || admit(conn, &clients));
+        if let Err(e) = spawned {
+            debug!("WebSocket handshake thread failed to start: {e}");
+        }
+    }
+}
+
+/// Completes the handshake and adds the client to the broadcast list.
+fn admit(mut conn: TcpStream, clients: &Mutex<Vec<TcpStream>>) {
+    match handshake(&mut conn).and_then(|()| conn.set_nonblocking(true)) {
+        Ok(()) => {
+            debug!("WebSocket client {:?} connected", conn.peer_addr().ok());
+            clients.lock().unwrap_or_else(|e| e.into_inner()).push(conn);
+        }
+        Err(e) => debug!("WebSocket handshake failed: {e}"),
+    }
+}
+
+/// Reads the HTTP upgrade request and answers it with `101 Switching Protocols`.
+fn handshake(conn: &mut TcpStream) -> io::Result<()> {
+    conn.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    conn.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    let mut req = Vec::new();
+    let mut buf = [0u8; 512];
+    while !req.windows(4).any(|w| w == b"\r\n\r\n") {
+        if req.len() >= 8192 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request too large",
+            ));
+        }
+        match conn.read(&mut buf)? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => req.extend_from_slice(&buf[..n]),
+        }
+    }
+    let req = String::from_utf8_lossy(&req);
+    let key = req.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("sec-websocket-key")
+            .then(|| value.trim())
+    });
+    let Some(key) = key else {
+        conn.write_all(b"HTTP/1.1 400 Bad Request\r\nConnection: close\r\n\r\n")?;
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a WebSocket upgrade",
+        ));
+    };
+    write!(
+        conn,
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(key)
+    )
+}
+
+/// `Sec-WebSocket-Accept` value for a client's `Sec-WebSocket-Key` (RFC 6455 §4.2.2).
+fn accept_key(key: &str) -> String {
+    let digest = Sha1::new()
+        .chain_update(key.as_bytes())
+        .chain_update(WS_GUID.as_bytes())
+        .finalize();
+    base64(&digest)
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, b[0], b[1], b[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Unmasked, unfragmented server-to-client frame.
+fn ws_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 10);
+    out.push(0x80 | opcode);
+    match payload.len() {
+        n @ 0..=125 => out.push(n as u8),
+        n @ 126..=0xFFFF => {
+            out.push(126);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            out.push(127);
+            out.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(payload);
+    out
+}
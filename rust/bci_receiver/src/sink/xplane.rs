@@ -0,0 +1,78 @@
+//! X-Plane `DATA` UDP input, for flying the sim instead of a real vehicle.
+//!
+//! A `DATA` packet is the 5-byte header `DATA\0` followed by 36-byte records:
+//! an `i32` data-set index and eight `f32` values, all little-endian. A value
+//! of -999 leaves that field untouched.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use super::ControlSink;
+use crate::ControlFrame;
+
+const HEADER: &[u8; 5] = b"DATA\0";
+const NO_CHANGE: f32 = -999.0;
+/// "Joystick aileron/elevator/rudder": elevator, aileron, rudder.
+const SET_JOYSTICK: i32 = 8;
+/// "Throttle (commanded)": one value per engine, 0..1.
+const SET_THROTTLE: i32 = 25;
+
+/// Sends pitch, roll and yaw as joystick elevator, aileron and rudder, and
+/// throttle (mapped to 0..1) as the commanded throttle of all engines.
+#[derive(Debug)]
+pub struct XPlaneSink {
+    sock: UdpSocket,
+    target: SocketAddr,
+}
+
+impl XPlaneSink {
+    pub fn new(target: impl ToSocketAddrs) -> io::Result<Self> {
+        let target = target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no X-Plane target"))?;
+        let local: SocketAddr = if target.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
+        };
+        Ok(XPlaneSink {
+            sock: UdpSocket::bind(local)?,
+            target,
+        })
+    }
+}
+
+impl ControlSink for XPlaneSink {
+    fn name(&self) -> &str {
+        "xplane"
+    }
+
+    fn send(&mut self, f: &ControlFrame) -> io::Result<()> {
+        let pkt = data_packet(f);
+        self.sock.send_to(&pkt, self.target).map(|_| ())
+    }
+
+    /// Leaves the controls at the failsafe frame.
+    fn close(&mut self, safe: &ControlFrame) -> io::Result<()> {
+        self.send(safe)
+    }
+}
+
+fn data_packet(f: &ControlFrame) -> Vec<u8> {
+    let mut joystick = [NO_CHANGE; 8];
+    joystick[0] = f.pitch.clamp(-1.0, 1.0);
+    joystick[1] = f.roll.clamp(-1.0, 1.0);
+    joystick[2] = f.yaw.clamp(-1.0, 1.0);
+    let throttle = [(f.throttle.clamp(-1.0, 1.0) + 1.0) * 0.5; 8];
+
+    let mut pkt = Vec::with_capacity(HEADER.len() + 2 * 36);
+    pkt.extend_from_slice(HEADER);
+    for (index, values) in [(SET_JOYSTICK, joystick), (SET_THROTTLE, throttle)] {
+        pkt.extend_from_slice(&index.to_le_bytes());
+        for v in values {
+            pkt.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    pkt
+}
@@ -0,0 +1,676 @@
+//! Counters describing the health of the incoming stream.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering, Ordering::Relaxed};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use log::info;
+
+use crate::unix_now;
+
+/// Stream counters, shared between the receive loop and any reporters.
+#[derive(Debug, Default)]
+pub struct Stats {
+    packets: AtomicU64,
+    utf8_errors: AtomicU64,
+    json_errors: AtomicU64,
+    binary_errors: AtomicU64,
+    msgpack_errors: AtomicU64,
+    auth_failures: AtomicU64,
+    decrypt_failures: AtomicU64,
+    foreign: AtomicU64,
+    oversized: AtomicU64,
+    stale: AtomicU64,
+    late: AtomicU64,
+    invalid: AtomicU64,
+    unknown_target: AtomicU64,
+    unsupported_version: AtomicU64,
+    clamped: AtomicU64,
+    gated: AtomicU64,
+    skewed: AtomicU64,
+    failsafe_trips: AtomicU64,
+    events: AtomicU64,
+    malformed_events: AtomicU64,
+    estops: AtomicU64,
+    frames_out: AtomicU64,
+    latency: LatencyHistogram,
+    window_latency: LatencyHistogram,
+    /// UNIX time of the last accepted packet in microseconds, 0 before the first.
+    last_valid_us: AtomicU64,
+    /// 0 when arming is not required, otherwise 1 disarmed / 2 armed.
+    arm_state: AtomicU8,
+    in_failsafe: AtomicBool,
+    estopped: AtomicBool,
+    max_gap_us: AtomicU64,
+    window_max_gap_us: AtomicU64,
+    sinks: Mutex<Vec<SinkDrops>>,
+}
+
+/// Output frames a sink was too slow to take, replaced by newer ones.
+#[derive(Debug)]
+struct SinkDrops {
+    name: String,
+    total: u64,
+    window: u64,
+}
+
+/// Handle for [`Stats::record_dropped`], returned by [`Stats::register_sink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SinkId(usize);
+
+/// Point-in-time copy of [`Stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    pub packets: u64,
+    pub utf8_errors: u64,
+    pub json_errors: u64,
+    pub binary_errors: u64,
+    pub msgpack_errors: u64,
+    pub auth_failures: u64,
+    pub decrypt_failures: u64,
+    /// Datagrams from a sender other than the configured source.
+    pub foreign: u64,
+    /// Datagrams longer than the receive buffer.
+    pub oversized: u64,
+    pub stale: u64,
+    /// Packets that reached the jitter buffer after newer ones had left it.
+    pub late: u64,
+    /// Packets with NaN or infinite values, or missing a field their schema
+    /// version requires.
+    pub invalid: u64,
+    /// Packets addressed to a target with no route.
+    pub unknown_target: u64,
+    /// Packets declaring a schema version this receiver doesn't know.
+    pub unsupported_version: u64,
+    pub clamped: u64,
+    pub gated: u64,
+    /// Packets stamped later than their arrival, left out of the latency figures.
+    pub skewed: u64,
+    pub failsafe_trips: u64,
+    /// Well-formed events, dispatched to the handlers.
+    pub events: u64,
+    /// Events that didn't parse; the rest of their packet was still used.
+    pub malformed_events: u64,
+    pub estops: u64,
+    /// Frames handed to the sinks by the output stage.
+    pub frames_out: u64,
+    /// UNIX time of the last accepted packet.
+    pub last_valid: Option<Duration>,
+    /// Whether the receiver is armed, `None` if arming is not required.
+    pub armed: Option<bool>,
+    /// The failsafe is currently overriding the input.
+    pub in_failsafe: bool,
+    /// An emergency stop is holding the failsafe frame until re-armed.
+    pub estopped: bool,
+    pub max_gap: Duration,
+    /// End-to-end latency, `None` before the first measurement.
+    pub latency: Option<LatencySummary>,
+}
+
+/// Distribution of packet latencies (arrival time minus the sender's `ts`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub samples: u64,
+    pub min: Duration,
+    pub mean: Duration,
+    /// 99th percentile, accurate to within an eighth of its value.
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+/// Cumulative latency counts since startup, the shape of a Prometheus histogram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyBuckets {
+    /// `(upper bound in seconds, samples at or below it)`, ascending and
+    /// ending with an infinite bound that holds every sample.
+    pub le: Vec<(f64, u64)>,
+    pub count: u64,
+    /// Sum of all samples in seconds.
+    pub sum: f64,
+}
+
+impl Stats {
+    pub fn record_packet(&self, gap: Option<Duration>) {
+        self.packets.fetch_add(1, Relaxed);
+        if let Some(gap) = gap {
+            let us = gap.as_micros().min(u64::MAX as u128) as u64;
+            self.max_gap_us.fetch_max(us, Relaxed);
+            self.window_max_gap_us.fetch_max(us, Relaxed);
+        }
+    }
+
+    /// The record functions for dropped packets return the new total of that kind.
+    pub fn record_utf8_error(&self) -> u64 {
+        self.utf8_errors.fetch_add(1, Relaxed) + 1
+    }
+
+    pub fn record_json_error(&self) -> u64 {
+        self.json_errors.fetch_add(1, Relaxed) + 1
+    }
+
+    pub fn record_binary_error(&self) -> u64 {
+        self.binary_errors.fetch_add(1, Relaxed) + 1
+    }
+
+    pub fn record_msgpack_error(&self) -> u64 {
+        self.msgpack_errors.fetch_add(1, Relaxed) + 1
+    }
+
+    /// A packet failed HMAC verification. Returns the new total.
+    pub fn record_auth_failure(&self) -> u64 {
+        self.auth_failures.fetch_add(1, Relaxed) + 1
+    }
+
+    /// A packet failed AES-GCM decryption. Returns the new total.
+    pub fn record_decrypt_failure(&self) -> u64 {
+        self.decrypt_failures.fetch_add(1, Relaxed) + 1
+    }
+
+    /// A datagram from an unexpected sender was dropped. Returns the new total.
+    pub fn record_foreign(&self) -> u64 {
+        self.foreign.fetch_add(1, Relaxed) + 1
+    }
+
+    /// A datagram too long for the receive buffer was dropped. Returns the new total.
+    pub fn record_oversized(&self) -> u64 {
+        self.oversized.fetch_add(1, Relaxed) + 1
+    }
+
+    /// A duplicated or out-of-order packet was dropped.
+    pub fn record_stale(&self) {
+        self.stale.fetch_add(1, Relaxed);
+    }
+
+    /// A packet arrived too late for the jitter buffer. Returns the new total.
+    pub fn record_late(&self) -> u64 {
+        self.late.fetch_add(1, Relaxed) + 1
+    }
+
+    /// A packet was rejected for carrying NaN or infinite values, or for
+    /// lacking a field its schema version requires. Returns the new total.
+    pub fn record_invalid(&self) -> u64 {
+        self.invalid.fetch_add(1, Relaxed) + 1
+    }
+
+    /// A packet named a target that has no route. Returns the new total.
+    pub fn record_unknown_target(&self) -> u64 {
+        self.unknown_target.fetch_add(1, Relaxed) + 1
+    }
+
+    /// A packet declared an unknown schema version. Returns the new total.
+    pub fn record_unsupported_version(&self) -> u64 {
+        self.unsupported_version.fetch_add(1, Relaxed) + 1
+    }
+
+    /// `n` axis values were clamped into range. Returns the new total.
+    pub fn record_clamped(&self, n: u64) -> u64 {
+        self.clamped.fetch_add(n, Relaxed) + n
+    }
+
+    /// A frame fell below the confidence threshold.
+    pub fn record_gated(&self) {
+        self.gated.fetch_add(1, Relaxed);
+    }
+
+    /// A packet made it through the pipeline; `unix_ts` is its arrival time.
+    pub fn record_valid(&self, unix_ts: f64) {
+        self.last_valid_us
+            .store((unix_ts * 1e6).max(1.0) as u64, Relaxed);
+    }
+
+    /// Records the latency of an accepted packet. A negative value means the
+    /// sender's clock is ahead of ours; it is counted as skewed instead, and
+    /// the new skewed total is returned.
+    pub fn record_latency(&self, seconds: f64) -> Option<u64> {
+        if seconds < 0.0 {
+            return Some(self.skewed.fetch_add(1, Relaxed) + 1);
+        }
+        let us = (seconds * 1e6) as u64;
+        self.latency.record(us);
+        self.window_latency.record(us);
+        None
+    }
+
+    /// The failsafe switched to neutral output.
+    pub fn record_failsafe(&self) {
+        self.failsafe_trips.fetch_add(1, Relaxed);
+        self.in_failsafe.store(true, Relaxed);
+    }
+
+    /// A valid packet ended the failsafe.
+    pub fn record_recovery(&self) {
+        self.in_failsafe.store(false, Relaxed);
+    }
+
+    /// An event was dispatched.
+    pub fn record_event(&self) {
+        self.events.fetch_add(1, Relaxed);
+    }
+
+    /// An event didn't parse. Returns the new total.
+    pub fn record_malformed_event(&self) -> u64 {
+        self.malformed_events.fetch_add(1, Relaxed) + 1
+    }
+
+    /// An `estop` event arrived.
+    pub fn record_estop(&self) {
+        self.estops.fetch_add(1, Relaxed);
+        self.estopped.store(true, Relaxed);
+    }
+
+    /// Re-arming released the emergency stop.
+    pub fn clear_estop(&self) {
+        self.estopped.store(false, Relaxed);
+    }
+
+    pub fn set_armed(&self, armed: bool) {
+        self.arm_state.store(if armed { 2 } else { 1 }, Relaxed);
+    }
+
+    /// Starts counting dropped frames for the sink called `name`.
+    pub fn register_sink(&self, name: &str) -> SinkId {
+        let mut sinks = self.sinks.lock().unwrap_or_else(|e| e.into_inner());
+        sinks.push(SinkDrops {
+            name: name.to_owned(),
+            total: 0,
+            window: 0,
+        });
+        SinkId(sinks.len() - 1)
+    }
+
+    /// `n` frames were dropped before `sink` could take them. Returns that sink's new total.
+    pub fn record_dropped(&self, sink: SinkId, n: u64) -> u64 {
+        let mut sinks = self.sinks.lock().unwrap_or_else(|e| e.into_inner());
+        let s = &mut sinks[sink.0];
+        s.total += n;
+        s.window += n;
+        s.total
+    }
+
+    /// Latencies since startup, counted against `bounds` (seconds, ascending)
+    /// and a final `+Inf`. Each sample counts against the first bound at or
+    /// above the upper edge of its internal bucket, so it may land one bound
+    /// high but never low.
+    pub fn latency_buckets(&self, bounds: &[f64]) -> LatencyBuckets {
+        self.latency.cumulative(bounds)
+    }
+
+    /// Dropped frames per sink since startup.
+    pub fn dropped_frames(&self) -> Vec<(String, u64)> {
+        let sinks = self.sinks.lock().unwrap_or_else(|e| e.into_inner());
+        sinks.iter().map(|s| (s.name.clone(), s.total)).collect()
+    }
+
+    /// Dropped frames per sink since the previous call, resetting the window.
+    fn take_window_dropped(&self) -> Vec<(String, u64)> {
+        let mut sinks = self.sinks.lock().unwrap_or_else(|e| e.into_inner());
+        sinks
+            .iter_mut()
+            .map(|s| (s.name.clone(), std::mem::take(&mut s.window)))
+            .collect()
+    }
+
+    /// Totals since startup, including per-sink drops, for the end-of-session log.
+    pub fn summary(&self) -> String {
+        format!("{}{}", self.snapshot(), Dropped(&self.dropped_frames()))
+    }
+
+    /// The output stage pushed one frame to the sinks.
+    pub fn record_output(&self) {
+        self.frames_out.fetch_add(1, Relaxed);
+    }
+
+    /// Totals since startup.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            packets: self.packets.load(Relaxed),
+            utf8_errors: self.utf8_errors.load(Relaxed),
+            json_errors: self.json_errors.load(Relaxed),
+            binary_errors: self.binary_errors.load(Relaxed),
+            msgpack_errors: self.msgpack_errors.load(Relaxed),
+            auth_failures: self.auth_failures.load(Relaxed),
+            decrypt_failures: self.decrypt_failures.load(Relaxed),
+            foreign: self.foreign.load(Relaxed),
+            oversized: self.oversized.load(Relaxed),
+            stale: self.stale.load(Relaxed),
+            late: self.late.load(Relaxed),
+            invalid: self.invalid.load(Relaxed),
+            unknown_target: self.unknown_target.load(Relaxed),
+            unsupported_version: self.unsupported_version.load(Relaxed),
+            clamped: self.clamped.load(Relaxed),
+            gated: self.gated.load(Relaxed),
+            skewed: self.skewed.load(Relaxed),
+            failsafe_trips: self.failsafe_trips.load(Relaxed),
+            events: self.events.load(Relaxed),
+            malformed_events: self.malformed_events.load(Relaxed),
+            estops: self.estops.load(Relaxed),
+            frames_out: self.frames_out.load(Relaxed),
+            last_valid: match self.last_valid_us.load(Relaxed) {
+                0 => None,
+                us => Some(Duration::from_micros(us)),
+            },
+            armed: match self.arm_state.load(Relaxed) {
+                0 => None,
+                s => Some(s == 2),
+            },
+            in_failsafe: self.in_failsafe.load(Relaxed),
+            estopped: self.estopped.load(Relaxed),
+            max_gap: Duration::from_micros(self.max_gap_us.load(Relaxed)),
+            latency: self.latency.summary(AtomicU64::load),
+        }
+    }
+
+    /// Largest gap since the previous call, resetting the window.
+    fn take_window_max_gap(&self) -> Duration {
+        Duration::from_micros(self.window_max_gap_us.swap(0, Relaxed))
+    }
+
+    /// Latency since the previous call, resetting the window.
+    fn take_window_latency(&self) -> Option<LatencySummary> {
+        self.window_latency.take()
+    }
+}
+
+impl StatsSnapshot {
+    /// Counter increments between `earlier` and `self`. `last_valid`, `armed`,
+    /// `in_failsafe`, `estopped`, `max_gap` and `latency` are left as in `self`.
+    pub fn since(&self, earlier: &StatsSnapshot) -> StatsSnapshot {
+        StatsSnapshot {
+            packets: self.packets - earlier.packets,
+            utf8_errors: self.utf8_errors - earlier.utf8_errors,
+            json_errors: self.json_errors - earlier.json_errors,
+            binary_errors: self.binary_errors - earlier.binary_errors,
+            msgpack_errors: self.msgpack_errors - earlier.msgpack_errors,
+            auth_failures: self.auth_failures - earlier.auth_failures,
+            decrypt_failures: self.decrypt_failures - earlier.decrypt_failures,
+            foreign: self.foreign - earlier.foreign,
+            oversized: self.oversized - earlier.oversized,
+            stale: self.stale - earlier.stale,
+            late: self.late - earlier.late,
+            invalid: self.invalid - earlier.invalid,
+            unknown_target: self.unknown_target - earlier.unknown_target,
+            unsupported_version: self.unsupported_version - earlier.unsupported_version,
+            clamped: self.clamped - earlier.clamped,
+            gated: self.gated - earlier.gated,
+            skewed: self.skewed - earlier.skewed,
+            failsafe_trips: self.failsafe_trips - earlier.failsafe_trips,
+            events: self.events - earlier.events,
+            malformed_events: self.malformed_events - earlier.malformed_events,
+            estops: self.estops - earlier.estops,
+            frames_out: self.frames_out - earlier.frames_out,
+            last_valid: self.last_valid,
+            armed: self.armed,
+            in_failsafe: self.in_failsafe,
+            estopped: self.estopped,
+            max_gap: self.max_gap,
+            latency: self.latency,
+        }
+    }
+}
+
+impl fmt::Display for StatsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} packets, {} utf8 errors, {} json errors, {} binary errors, {} msgpack errors, {} auth failures, \
+             {} decrypt failures, {} foreign, {} oversized, {} invalid, {} unknown target, {} unsupported version, {} stale, {} late, {} clamped, {} gated, {} skewed, {} failsafe trips, \
+             {} events, {} malformed events, {} estops, max gap {} ms",
+            self.packets,
+            self.utf8_errors,
+            self.json_errors,
+            self.binary_errors,
+            self.msgpack_errors,
+            self.auth_failures,
+            self.decrypt_failures,
+            self.foreign,
+            self.oversized,
+            self.invalid,
+            self.unknown_target,
+            self.unsupported_version,
+            self.stale,
+            self.late,
+            self.clamped,
+            self.gated,
+            self.skewed,
+            self.failsafe_trips,
+            self.events,
+            self.malformed_events,
+            self.estops,
+            self.max_gap.as_millis()
+        )?;
+        if let Some(l) = self.latency {
+            let ms = |d: Duration| d.as_secs_f64() * 1e3;
+            write!(
+                f,
+                ", latency min/mean/p99/max {:.1}/{:.1}/{:.1}/{:.1} ms",
+                ms(l.min),
+                ms(l.mean),
+                ms(l.p99),
+                ms(l.max)
+            )?;
+        }
+        self.write_state(f)
+    }
+}
+
+impl StatsSnapshot {
+    /// Appends `, failsafe` and/or `, armed`/`, disarmed` as applicable.
+    fn write_state(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.in_failsafe {
+            f.write_str(", failsafe")?;
+        }
+        if self.estopped {
+            f.write_str(", estop")?;
+        }
+        match self.armed {
+            Some(true) => f.write_str(", armed"),
+            Some(false) => f.write_str(", disarmed"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Health line for an interval in which nothing arrived, so a quiet log
+/// still shows the receiver is alive.
+struct Idle<'a> {
+    snap: &'a StatsSnapshot,
+    now: f64,
+}
+
+impl fmt::Display for Idle<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.snap.last_valid {
+            Some(t) => write!(
+                f,
+                "idle, last packet {:.1} s ago",
+                (self.now - t.as_secs_f64()).max(0.0)
+            )?,
+            None => f.write_str("idle, no packet received yet")?,
+        }
+        self.snap.write_state(f)
+    }
+}
+
+/// Formats the sinks that dropped frames as `, dropped frames: csv 3, …`.
+struct Dropped<'a>(&'a [(String, u64)]);
+
+impl fmt::Display for Dropped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = ", dropped frames: ";
+        for (name, n) in self.0.iter().filter(|(_, n)| *n > 0) {
+            write!(f, "{sep}{name} {n}")?;
+            sep = ", ";
+        }
+        Ok(())
+    }
+}
+
+/// Prints a one-line summary of the last interval whenever it is due, or a
+/// short health line if nothing arrived in it.
+#[derive(Debug)]
+pub struct Reporter {
+    interval: Duration,
+    last_at: Instant,
+    last: StatsSnapshot,
+}
+
+impl Reporter {
+    pub fn new(interval: Duration, now: Instant) -> Self {
+        Reporter {
+            interval,
+            last_at: now,
+            last: StatsSnapshot::default(),
+        }
+    }
+
+    pub fn maybe_report(&mut self, stats: &Stats, now: Instant) {
+        let elapsed = now.duration_since(self.last_at);
+        if elapsed < self.interval {
+            return;
+        }
+        let cur = stats.snapshot();
+        let window = StatsSnapshot {
+            max_gap: stats.take_window_max_gap(),
+            latency: stats.take_window_latency(),
+            ..cur.since(&self.last)
+        };
+        let rate = window.packets as f64 / elapsed.as_secs_f64();
+        let dropped = stats.take_window_dropped();
+        if window.packets == 0 {
+            let idle = Idle {
+                snap: &cur,
+                now: unix_now(),
+            };
+            info!("{idle}{}", Dropped(&dropped));
+        } else {
+            info!("{rate:.1} pkt/s, {window}{}", Dropped(&dropped));
+        }
+        self.last = cur;
+        self.last_at = now;
+    }
+}
+
+/// Exact buckets below 16 µs, then eight per power of two up to about a minute.
+const LATENCY_BUCKETS: usize = 192;
+
+/// Samples are capped at an hour so that a sender with a wildly wrong clock
+/// can't overflow the running sum.
+const MAX_LATENCY_US: u64 = 3_600_000_000;
+
+/// Log-linear histogram of latencies in microseconds.
+#[derive(Debug)]
+struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS],
+    count: AtomicU64,
+    sum_us: AtomicU64,
+    min_us: AtomicU64,
+    max_us: AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            count: AtomicU64::new(0),
+            sum_us: AtomicU64::new(0),
+            min_us: AtomicU64::new(u64::MAX),
+            max_us: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    fn record(&self, us: u64) {
+        let us = us.min(MAX_LATENCY_US);
+        self.buckets[bucket_of(us)].fetch_add(1, Relaxed);
+        self.count.fetch_add(1, Relaxed);
+        self.sum_us.fetch_add(us, Relaxed);
+        self.min_us.fetch_min(us, Relaxed);
+        self.max_us.fetch_max(us, Relaxed);
+    }
+
+    /// Summarizes and clears the histogram. Samples recorded concurrently may
+    /// land in either window.
+    fn take(&self) -> Option<LatencySummary> {
+        let summary = self.summary(|a, o| a.swap(0, o));
+        self.min_us.store(u64::MAX, Relaxed);
+        summary
+    }
+
+    fn summary(&self, read: impl Fn(&AtomicU64, Ordering) -> u64) -> Option<LatencySummary> {
+        let count = read(&self.count, Relaxed);
+        if count == 0 {
+            return None;
+        }
+        let buckets = self.buckets.each_ref().map(|b| read(b, Relaxed));
+        let min = self.min_us.load(Relaxed);
+        let max = read(&self.max_us, Relaxed);
+        let rank = count.saturating_mul(99).div_ceil(100);
+        let mut seen = 0;
+        let p99 = buckets
+            .iter()
+            .position(|&n| {
+                seen += n;
+                seen >= rank
+            })
+            // The last bucket also holds everything beyond its range.
+            .map_or(max, |i| bucket_upper(i).clamp(min, max));
+        Some(LatencySummary {
+            samples: count,
+            min: Duration::from_micros(min),
+            mean: Duration::from_micros(read(&self.sum_us, Relaxed) / count),
+            p99: Duration::from_micros(p99),
+            max: Duration::from_micros(max),
+        })
+    }
+}
+
+impl LatencyHistogram {
+    fn cumulative(&self, bounds: &[f64]) -> LatencyBuckets {
+        let mut le: Vec<(f64, u64)> = bounds.iter().map(|&b| (b, 0)).collect();
+        le.push((f64::INFINITY, 0));
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            let n = bucket.load(Relaxed);
+            if n == 0 {
+                continue;
+            }
+            // The last bucket also holds everything beyond its range.
+            let upper = if i == LATENCY_BUCKETS - 1 {
+                f64::INFINITY
+            } else {
+                bucket_upper(i) as f64 / 1e6
+            };
+            for (bound, count) in &mut le {
+                if upper <= *bound {
+                    *count += n;
+                }
+            }
+        }
+        LatencyBuckets {
+            // Taken from the buckets so it matches `+Inf` under concurrent updates.
+            count: le.last().map_or(0, |&(_, n)| n),
+            le,
+            sum: self.sum_us.load(Relaxed) as f64 / 1e6,
+        }
+    }
+}
+
+fn bucket_of(us: u64) -> usize {
+    if us < 16 {
+        return us as usize;
+    }
+    let e = u64::from(us.ilog2());
+    let sub = (us >> (e - 3)) & 7;
+    ((e - 4) * 8 + 16 + sub).min(LATENCY_BUCKETS as u64 - 1) as usize
+}
+
+/// Largest value that falls into bucket `i`.
+fn bucket_upper(i: usize) -> u64 {
+    if i < 16 {
+        return i as u64;
+    }
+    let e = (i as u64 - 16) / 8 + 4;
+    let sub = (i as u64 - 16) % 8;
+    ((9 + sub) << (e - 3)) - 1
+}
@@ -0,0 +1,56 @@
+use bci_receiver::arm::{ArmState, Arming};
+
+fn feed(arming: &mut Arming, arm: Option<bool>, n: usize) -> ArmState {
+    (0..n).fold(arming.state(), |_, _| arming.update(arm))
+}
+
+#[test]
+fn arms_after_n_consecutive_requests() {
+    let mut arming = Arming::new(3);
+    assert_eq!(feed(&mut arming, Some(true), 2), ArmState::Disarmed);
+    assert_eq!(arming.update(Some(true)), ArmState::Armed);
+    assert_eq!(arming.update(Some(true)), ArmState::Armed);
+}
+
+#[test]
+fn interleaved_disarm_or_gap_restarts_the_count() {
+    let mut arming = Arming::new(3);
+    feed(&mut arming, Some(true), 2);
+    assert_eq!(arming.update(Some(false)), ArmState::Disarmed);
+    assert_eq!(feed(&mut arming, Some(true), 2), ArmState::Disarmed);
+    assert_eq!(arming.update(None), ArmState::Disarmed);
+    assert_eq!(feed(&mut arming, Some(true), 2), ArmState::Disarmed);
+    assert_eq!(arming.update(Some(true)), ArmState::Armed);
+}
+
+#[test]
+fn disarm_is_immediate_and_missing_field_keeps_the_state() {
+    let mut arming = Arming::new(2);
+    feed(&mut arming, Some(true), 2);
+    assert_eq!(arming.update(None), ArmState::Armed);
+    assert_eq!(arming.update(Some(false)), ArmState::Disarmed);
+}
+
+#[test]
+fn latch_holds_until_the_sender_lets_go_of_arm() {
+    let mut arming = Arming::new(2);
+    feed(&mut arming, Some(true), 2);
+    arming.latch();
+    assert_eq!(arming.state(), ArmState::Disarmed);
+    assert_eq!(feed(&mut arming, Some(true), 10), ArmState::Disarmed);
+    // Letting go clears the latch; a fresh streak arms again.
+    assert_eq!(arming.update(Some(false)), ArmState::Disarmed);
+    assert_eq!(arming.update(Some(true)), ArmState::Disarmed);
+    assert_eq!(arming.update(Some(true)), ArmState::Armed);
+
+    arming.latch();
+    assert_eq!(arming.update(None), ArmState::Disarmed);
+    assert_eq!(feed(&mut arming, Some(true), 2), ArmState::Armed);
+}
+
+#[test]
+fn zero_required_packets_still_needs_one() {
+    let mut arming = Arming::new(0);
+    assert_eq!(arming.state(), ArmState::Disarmed);
+    assert_eq!(arming.update(Some(true)), ArmState::Armed);
+}
@@ -0,0 +1,69 @@
+use bci_receiver::auth::{AuthError, Authenticator};
+use bci_receiver::codec::{encode_binary, parse_json};
+use bci_receiver::Format;
+
+const MSG: &str = r#"{"yaw":0.25,"altitude":0.0,"throttle":0.5,"ts":1.0}"#;
+
+#[test]
+fn signed_json_verifies() {
+    let auth = Authenticator::new(b"secret");
+    let signed = auth.sign_json(MSG);
+    assert_eq!(
+        auth.verify(signed.as_bytes(), Format::Json),
+        Ok(signed.as_bytes())
+    );
+    assert_eq!(
+        auth.verify(signed.as_bytes(), Format::Auto),
+        Ok(signed.as_bytes())
+    );
+}
+
+#[test]
+fn signed_binary_verifies_and_strips_the_tag() {
+    let auth = Authenticator::new(b"secret");
+    let payload = encode_binary(&parse_json(MSG.as_bytes()).unwrap());
+    let signed = auth.sign_binary(&payload);
+    assert_eq!(auth.verify(&signed, Format::Binary), Ok(&payload[..]));
+    assert_eq!(auth.verify(&signed, Format::Auto), Ok(&payload[..]));
+}
+
+#[test]
+fn tampered_payload_is_a_mismatch() {
+    let auth = Authenticator::new(b"secret");
+    let signed = auth.sign_json(MSG).replace("0.25", "0.75");
+    assert_eq!(
+        auth.verify(signed.as_bytes(), Format::Json),
+        Err(AuthError::Mismatch)
+    );
+
+    let payload = encode_binary(&parse_json(MSG.as_bytes()).unwrap());
+    let mut signed = auth.sign_binary(&payload);
+    signed[0] ^= 1;
+    assert_eq!(
+        auth.verify(&signed, Format::Binary),
+        Err(AuthError::Mismatch)
+    );
+}
+
+#[test]
+fn wrong_secret_is_a_mismatch() {
+    let signed = Authenticator::new(b"secret").sign_json(MSG);
+    assert_eq!(
+        Authenticator::new(b"other").verify(signed.as_bytes(), Format::Json),
+        Err(AuthError::Mismatch)
+    );
+}
+
+#[test]
+fn unsigned_packet_is_missing() {
+    let auth = Authenticator::new(b"secret");
+    assert_eq!(
+        auth.verify(MSG.as_bytes(), Format::Json),
+        Err(AuthError::Missing)
+    );
+    let payload = encode_binary(&parse_json(MSG.as_bytes()).unwrap());
+    assert_eq!(
+        auth.verify(&payload, Format::Binary),
+        Err(AuthError::Missing)
+    );
+}
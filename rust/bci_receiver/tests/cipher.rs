@@ -0,0 +1,40 @@
+use bci_receiver::cipher::{Cipher, CipherError, NONCE_LEN, TAG_LEN};
+
+const MSG: &[u8] = br#"{"yaw":0.25,"altitude":0.0,"throttle":0.5,"ts":1.0}"#;
+const NONCE: [u8; NONCE_LEN] = [7; NONCE_LEN];
+
+#[test]
+fn sealed_packet_opens() {
+    let cipher = Cipher::new(b"key");
+    let sealed = cipher.seal(&NONCE, MSG);
+    assert_eq!(sealed.len(), NONCE_LEN + MSG.len() + TAG_LEN);
+    assert_eq!(&sealed[..NONCE_LEN], &NONCE);
+    assert_eq!(cipher.open(&sealed).unwrap(), MSG);
+}
+
+#[test]
+fn wrong_key_is_rejected() {
+    let sealed = Cipher::new(b"key").seal(&NONCE, MSG);
+    assert_eq!(
+        Cipher::new(b"other").open(&sealed),
+        Err(CipherError::Decrypt)
+    );
+}
+
+#[test]
+fn flipped_tag_byte_is_rejected() {
+    let cipher = Cipher::new(b"key");
+    let mut sealed = cipher.seal(&NONCE, MSG);
+    *sealed.last_mut().unwrap() ^= 0x80;
+    assert_eq!(cipher.open(&sealed), Err(CipherError::Decrypt));
+}
+
+#[test]
+fn packet_shorter_than_nonce_and_tag_is_truncated() {
+    let cipher = Cipher::new(b"key");
+    let sealed = cipher.seal(&NONCE, b"");
+    assert_eq!(cipher.open(&sealed).unwrap(), b"");
+    let short = &sealed[..NONCE_LEN + TAG_LEN - 1];
+    assert_eq!(cipher.open(short), Err(CipherError::Truncated(short.len())));
+    assert_eq!(cipher.open(&NONCE[..4]), Err(CipherError::Truncated(4)));
+}
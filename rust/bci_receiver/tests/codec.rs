@@ -0,0 +1,53 @@
+use bci_receiver::codec::{encode_binary, parse_binary, parse_json, BINARY_LEN};
+use bci_receiver::{DecodeError, Format};
+
+const MSG: &[u8] =
+    br#"{"yaw":0.25,"altitude":-0.5,"pitch":0.75,"throttle":-1.0,"ts":1700000000.125}"#;
+
+#[test]
+fn binary_round_trips_every_field() {
+    let bytes = encode_binary(&parse_json(MSG).unwrap());
+    let msg = parse_binary(&bytes).unwrap();
+    assert_eq!(msg.yaw, 0.25);
+    assert_eq!(msg.altitude, -0.5);
+    assert_eq!(msg.pitch, Some(0.75));
+    assert_eq!(msg.throttle, Some(-1.0));
+    assert_eq!(msg.ts, 1700000000.125);
+    assert_eq!(msg.speed, None);
+}
+
+#[test]
+fn binary_layout_is_little_endian() {
+    let mut bytes = [0u8; BINARY_LEN];
+    for (i, v) in [0.1f32, 0.2, 0.3, 0.4].into_iter().enumerate() {
+        bytes[i * 4..i * 4 + 4].copy_from_slice(&v.to_le_bytes());
+    }
+    bytes[16..].copy_from_slice(&42.5f64.to_le_bytes());
+    let msg = parse_binary(&bytes).unwrap();
+    assert_eq!((msg.yaw, msg.altitude), (0.1, 0.2));
+    assert_eq!((msg.pitch, msg.throttle), (Some(0.3), Some(0.4)));
+    assert_eq!(msg.ts, 42.5);
+}
+
+#[test]
+fn binary_of_wrong_length_is_rejected() {
+    for len in [0, BINARY_LEN - 1, BINARY_LEN + 1] {
+        let bytes = vec![0u8; len];
+        assert!(matches!(
+            parse_binary(&bytes),
+            Err(DecodeError::BinaryLength(n)) if n == len
+        ));
+    }
+}
+
+#[test]
+fn auto_picks_binary_by_length() {
+    let bytes = encode_binary(&parse_json(MSG).unwrap());
+    assert_eq!(Format::Auto.parse(&bytes).unwrap().pitch, Some(0.75));
+    assert_eq!(Format::Auto.parse(MSG).unwrap().pitch, Some(0.75));
+    // Anything not exactly BINARY_LEN long is read as JSON.
+    assert!(matches!(
+        Format::Auto.parse(&bytes[..BINARY_LEN - 1]),
+        Err(DecodeError::Utf8(_) | DecodeError::Json(_))
+    ));
+}
@@ -0,0 +1,16 @@
+use bci_receiver::{AxisCurve, Config};
+
+#[test]
+fn deadzone_must_leave_part_of_the_range() {
+    let mut cfg = Config::default();
+    cfg.curves.yaw.deadzone = 0.99;
+    assert!(cfg.validate().is_ok());
+    for deadzone in [1.0, 1.5, -0.1, f32::NAN] {
+        cfg.curves.pitch = AxisCurve {
+            deadzone,
+            ..AxisCurve::LINEAR
+        };
+        let err = cfg.validate().unwrap_err();
+        assert!(err.contains("pitch deadzone"), "{err}");
+    }
+}
@@ -0,0 +1,68 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bci_receiver::sink::CsvSink;
+use bci_receiver::{ControlFrame, ControlSink};
+
+fn unix_now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("bci-{}-{name}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn writes_a_header_and_one_row_per_frame() {
+    let path = temp_path("frames.csv");
+    let mut sink = CsvSink::create(&path).unwrap();
+    let before = unix_now();
+    let frame = ControlFrame {
+        yaw: 0.5,
+        altitude: -0.25,
+        pitch: 0.125,
+        throttle: 1.0,
+        ts: 1234.5,
+        ..ControlFrame::NEUTRAL
+    };
+    sink.send(&frame).unwrap();
+    sink.send(&ControlFrame { yaw: -1.0, ..frame }).unwrap();
+    sink.close(&ControlFrame::NEUTRAL).unwrap();
+    let after = unix_now();
+
+    // `close` flushes, so everything is on disk while the sink still exists.
+    let text = fs::read_to_string(&path).unwrap();
+    drop(sink);
+    fs::remove_file(&path).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "recv_ts,yaw,altitude,pitch,throttle,ts");
+    assert_eq!(lines.len(), 3);
+
+    let cols: Vec<&str> = lines[1].split(',').collect();
+    assert_eq!(cols[1..], ["0.5", "-0.25", "0.125", "1", "1234.500000"]);
+    assert_eq!(lines[2].split(',').nth(1), Some("-1"));
+    // `recv_ts` is the emit time, with `.` as the decimal separator.
+    let recv_ts: f64 = cols[0].parse().unwrap();
+    assert!(cols[0].contains('.'));
+    assert!(
+        (before - 1e-6..=after + 1e-6).contains(&recv_ts),
+        "{recv_ts} not in {before}..{after}"
+    );
+}
+
+#[test]
+fn probe_writes_no_row() {
+    let path = temp_path("probe.csv");
+    let mut sink = CsvSink::create(&path).unwrap();
+    sink.probe(&ControlFrame::NEUTRAL).unwrap();
+    let text = fs::read_to_string(&path).unwrap();
+    drop(sink);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(text, "recv_ts,yaw,altitude,pitch,throttle,ts\n");
+}
@@ -0,0 +1,235 @@
+use bci_receiver::{
+    decode_msg, parse_msg, resolve_throttle, AltMode, AltRange, AxisCurve, AxisRange, BciEvent,
+    Calibration, ClimbIntegrator, Curves, DecodeError, Decoder, Invert, Sanitized,
+};
+
+#[test]
+fn explicit_throttle_wins_over_speed() {
+    assert_eq!(resolve_throttle(Some(0.25), Some(1.0)), 0.25);
+    assert_eq!(resolve_throttle(Some(-1.0), None), -1.0);
+}
+
+#[test]
+fn speed_maps_onto_throttle_range() {
+    assert_eq!(resolve_throttle(None, Some(0.0)), -1.0);
+    assert_eq!(resolve_throttle(None, Some(0.5)), 0.0);
+    assert_eq!(resolve_throttle(None, Some(1.0)), 1.0);
+    assert_eq!(resolve_throttle(None, Some(0.75)), 0.5);
+}
+
+#[test]
+fn missing_throttle_and_speed_is_idle() {
+    assert_eq!(resolve_throttle(None, None), -1.0);
+}
+
+#[test]
+fn out_of_range_speed_is_mapped_then_clamped() {
+    assert_eq!(resolve_throttle(None, Some(1.5)), 2.0);
+    assert_eq!(resolve_throttle(None, Some(-0.5)), -2.0);
+
+    let f = decode_msg(br#"{"yaw":0,"altitude":0,"speed":1.5,"ts":0}"#).unwrap();
+    assert_eq!(f.throttle, 1.0);
+    let f = decode_msg(br#"{"yaw":0,"altitude":0,"speed":-0.5,"ts":0}"#).unwrap();
+    assert_eq!(f.throttle, -1.0);
+}
+
+#[test]
+fn decode_resolves_throttle_from_speed() {
+    let f = decode_msg(br#"{"yaw":0.1,"altitude":0.2,"speed":0.75,"ts":1.5}"#).unwrap();
+    assert_eq!(f.throttle, 0.5);
+    assert_eq!(f.pitch, 0.0);
+    assert_eq!(f.roll, 0.0);
+    assert_eq!(f.ts, 1.5);
+}
+
+#[test]
+fn v2_requires_explicit_throttle() {
+    let f = decode_msg(br#"{"v":1,"yaw":0,"altitude":0,"speed":1,"ts":0}"#).unwrap();
+    assert_eq!(f.throttle, 1.0);
+    let err = decode_msg(br#"{"v":2,"yaw":0,"altitude":0,"speed":1,"ts":0}"#).unwrap_err();
+    assert!(matches!(err, DecodeError::Missing("throttle")));
+    let f = decode_msg(br#"{"v":2,"yaw":0,"altitude":0,"throttle":0.5,"speed":1,"ts":0}"#).unwrap();
+    assert_eq!(f.throttle, 0.5);
+}
+
+#[test]
+fn unknown_version_is_rejected() {
+    let err = decode_msg(br#"{"v":3,"yaw":0,"altitude":0,"throttle":0,"ts":0}"#).unwrap_err();
+    assert!(matches!(err, DecodeError::Version(3)));
+}
+
+fn decode_msg_with(d: &Decoder, bytes: &[u8]) -> Sanitized {
+    d.resolve(&d.parse(bytes).unwrap()).unwrap()
+}
+
+fn yaw_msg(yaw: f32) -> Vec<u8> {
+    format!(r#"{{"yaw":{yaw},"altitude":0,"ts":0}}"#).into_bytes()
+}
+
+#[test]
+fn expo_one_is_linear() {
+    for i in -10..=10 {
+        let x = i as f32 / 10.0;
+        assert_eq!(AxisCurve::LINEAR.apply(x), x);
+    }
+}
+
+#[test]
+fn expo_softens_the_center_and_keeps_the_sign() {
+    let c = AxisCurve {
+        expo: 2.0,
+        deadzone: 0.0,
+    };
+    assert_eq!(c.apply(0.5), 0.25);
+    assert_eq!(c.apply(-0.5), -0.25);
+    assert_eq!(c.apply(0.0), 0.0);
+    assert_eq!(c.apply(1.0), 1.0);
+    assert_eq!(c.apply(-1.0), -1.0);
+    for i in 1..10 {
+        let x = i as f32 / 10.0;
+        assert!(c.apply(x) < x, "expo 2 at {x}");
+        assert!(c.apply(-x) > -x, "expo 2 at {}", -x);
+    }
+}
+
+#[test]
+fn deadzone_is_continuous_at_the_threshold() {
+    let c = AxisCurve {
+        expo: 1.0,
+        deadzone: 0.2,
+    };
+    assert_eq!(c.apply(0.2), 0.0);
+    assert_eq!(c.apply(-0.2), 0.0);
+    assert_eq!(c.apply(0.1), 0.0);
+    assert!(c.apply(0.2 + 1e-4) > 0.0);
+    assert!(c.apply(0.2 + 1e-4) < 1e-3);
+    assert!(c.apply(-0.2 - 1e-4) > -1e-3);
+    assert!((c.apply(0.6) - 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn deadzone_keeps_full_deflection() {
+    for expo in [1.0, 2.0, 0.5] {
+        let c = AxisCurve {
+            expo,
+            deadzone: 0.3,
+        };
+        assert_eq!(c.apply(1.0), 1.0);
+        assert_eq!(c.apply(-1.0), -1.0);
+        assert_eq!(c.apply(4.0), 1.0);
+    }
+}
+
+#[test]
+fn inverting_yaw_negates_across_full_range() {
+    let curves = Curves {
+        yaw: AxisCurve {
+            expo: 2.0,
+            deadzone: 0.1,
+        },
+        ..Curves::default()
+    };
+    let plain = Decoder::new(curves);
+    let inverted = Decoder::new(curves).with_invert(Invert {
+        yaw: true,
+        ..Invert::default()
+    });
+    for i in -20..=20 {
+        let v = i as f32 / 20.0;
+        let a = plain.decode(&yaw_msg(v)).unwrap();
+        let b = inverted.decode(&yaw_msg(v)).unwrap();
+        assert_eq!(b.yaw, -a.yaw, "yaw {v}");
+        assert_eq!(b.pitch, a.pitch);
+        assert_eq!(b.throttle, a.throttle);
+    }
+}
+
+#[test]
+fn invert_composes_with_clamping() {
+    let d = Decoder::default().with_invert(Invert::parse_list("yaw,throttle").unwrap());
+    let msg = decode_msg_with(&d, br#"{"yaw":3.0,"altitude":0,"throttle":-5,"ts":0}"#);
+    assert_eq!(msg.frame.yaw, -1.0);
+    assert_eq!(msg.frame.throttle, 1.0);
+    assert_eq!(msg.clamped, 2);
+}
+
+#[test]
+fn invert_list_rejects_unknown_axes() {
+    assert!(Invert::parse_list("yaw,elevator").is_err());
+    assert_eq!(Invert::parse_list("").unwrap(), Invert::default());
+}
+
+#[test]
+fn default_calibration_is_an_exact_identity() {
+    for i in -100..=100 {
+        let x = i as f32 / 100.0;
+        assert_eq!(AxisRange::FULL.apply(x), x);
+    }
+    let f =
+        decode_msg(br#"{"yaw":0.1,"altitude":-0.3,"pitch":0.7,"roll":-1.0,"throttle":1.0,"ts":0}"#)
+            .unwrap();
+    assert_eq!(
+        (f.yaw, f.altitude, f.pitch, f.roll, f.throttle),
+        (0.1, -0.3, 0.7, -1.0, 1.0)
+    );
+}
+
+#[test]
+fn calibration_stretches_observed_range() {
+    let d = Decoder::default().with_calibration(Calibration {
+        yaw: AxisRange {
+            min: -0.8,
+            max: 0.5,
+        },
+        throttle: AxisRange {
+            min: -0.6,
+            max: 0.4,
+        },
+        ..Calibration::default()
+    });
+    let f = decode_msg_with(&d, br#"{"yaw":0.5,"altitude":0,"throttle":-0.1,"ts":0}"#).frame;
+    assert_eq!(f.yaw, 1.0);
+    assert!((f.throttle - 0.0).abs() < 1e-6);
+    // One linear map: the midpoint of the observed range becomes center.
+    let f = decode_msg_with(&d, &yaw_msg(-0.15)).frame;
+    assert!(f.yaw.abs() < 1e-6);
+    let f = decode_msg_with(&d, &yaw_msg(0.0)).frame;
+    assert!((f.yaw - 3.0 / 13.0).abs() < 1e-6);
+    assert_eq!(decode_msg_with(&d, &yaw_msg(-1.0)).frame.yaw, -1.0);
+}
+
+#[test]
+fn climb_rate_integrates_over_packet_time() {
+    let mut climb = ClimbIntegrator::new(AltRange {
+        mode: AltMode::Rate,
+        climb_rate: 2.0,
+        ..AltRange::new(1.0, 5.0)
+    });
+    assert_eq!(climb.update(1.0, 10.0), 1.0);
+    assert_eq!(climb.update(1.0, 10.5), 2.0);
+    assert_eq!(climb.update(0.0, 11.0), 2.0);
+    assert_eq!(climb.update(-0.5, 12.0), 1.0);
+    // Clamped to the range however long the axis is held.
+    assert_eq!(climb.update(1.0, 20.0), 5.0);
+    // After a reset the outage isn't integrated, the setpoint is kept.
+    climb.reset();
+    assert_eq!(climb.update(-1.0, 60.0), 5.0);
+    assert_eq!(climb.update(-1.0, 60.25), 4.5);
+}
+
+#[test]
+fn malformed_event_keeps_the_axes() {
+    let m = parse_msg(br#"{"yaw":0.5,"altitude":0,"event":{"blink":2},"ts":1}"#).unwrap();
+    assert_eq!(m.yaw, 0.5);
+    assert!(m.event.unwrap().parse::<BciEvent>().is_err());
+
+    let event = |s: &str| s.parse::<BciEvent>();
+    assert_eq!(event("estop"), Ok(BciEvent::Estop));
+    assert_eq!(event("toggle_mode"), Ok(BciEvent::ToggleMode));
+    assert_eq!(
+        event("jaw_clench"),
+        Ok(BciEvent::Custom("jaw_clench".into()))
+    );
+    assert!(event("").is_err());
+    assert!(event("Estop").is_err());
+}
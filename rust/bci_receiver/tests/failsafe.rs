@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+
+use bci_receiver::pipeline::Pipeline;
+use bci_receiver::{Config, ControlFrame};
+
+const TIMEOUT: Duration = Duration::from_millis(300);
+
+fn packet(yaw: f32, ts: f64) -> Vec<u8> {
+    format!(r#"{{"yaw":{yaw},"altitude":0,"throttle":0.5,"ts":{ts}}}"#).into_bytes()
+}
+
+fn pipeline(now: Instant) -> Pipeline {
+    pipeline_with(
+        Config {
+            failsafe_ms: TIMEOUT.as_millis() as u64,
+            ..Config::default()
+        },
+        now,
+    )
+}
+
+fn pipeline_with(cfg: Config, now: Instant) -> Pipeline {
+    Pipeline::new(&cfg, now).unwrap().without_latency()
+}
+
+#[test]
+fn neutral_is_emitted_once_the_stream_goes_stale() {
+    let t0 = Instant::now();
+    let mut p = pipeline(t0);
+    let live = p.on_datagram(&packet(0.5, 1.0), t0).unwrap();
+    assert_eq!((live.yaw, live.throttle, live.safe), (0.5, 0.5, false));
+    assert_eq!(p.poll(t0 + TIMEOUT - Duration::from_millis(1)), None);
+    let out = p.poll(t0 + TIMEOUT).unwrap();
+    assert_eq!(out, ControlFrame::NEUTRAL);
+    // It keeps being emitted for as long as nothing arrives.
+    assert_eq!(p.poll(t0 + 10 * TIMEOUT), Some(ControlFrame::NEUTRAL));
+}
+
+#[test]
+fn live_input_resumes_on_the_next_packet() {
+    let t0 = Instant::now();
+    let mut p = pipeline(t0);
+    p.on_datagram(&packet(0.5, 1.0), t0).unwrap();
+    assert!(p.poll(t0 + TIMEOUT).is_some());
+    let back = p
+        .on_datagram(&packet(-0.25, 2.0), t0 + 2 * TIMEOUT)
+        .unwrap();
+    assert_eq!((back.yaw, back.safe), (-0.25, false));
+    assert_eq!(p.poll(t0 + 2 * TIMEOUT + Duration::from_millis(1)), None);
+}
+
+#[test]
+fn timeout_counts_from_startup_before_the_first_packet() {
+    // The output already starts at the failsafe frame; the pipeline only
+    // starts re-emitting it once a full timeout has passed without input.
+    let t0 = Instant::now();
+    let mut p = pipeline(t0);
+    assert_eq!(p.poll(t0 + TIMEOUT / 2), None);
+    assert_eq!(p.poll(t0 + TIMEOUT), Some(ControlFrame::NEUTRAL));
+}
+
+#[test]
+fn recovery_ramp_runs_across_packets_without_restarting() {
+    let t0 = Instant::now();
+    let mut p = pipeline_with(
+        Config {
+            failsafe_ms: TIMEOUT.as_millis() as u64,
+            recover_ms: 100,
+            ..Config::default()
+        },
+        t0,
+    );
+    // The very first packet isn't ramped: output was never live before it.
+    assert_eq!(p.on_datagram(&packet(1.0, 1.0), t0).unwrap().yaw, 1.0);
+    assert!(p.poll(t0 + TIMEOUT).is_some());
+    let back = t0 + 2 * TIMEOUT;
+    let ms = |n| back + Duration::from_millis(n);
+    let yaws: Vec<f32> = [(0, 2.0), (25, 2.1), (50, 2.2), (75, 2.3), (100, 2.4)]
+        .into_iter()
+        .map(|(at, ts)| p.on_datagram(&packet(1.0, ts), ms(at)).unwrap().yaw)
+        .collect();
+    for (yaw, want) in yaws.iter().zip([0.0, 0.25, 0.5, 0.75, 1.0]) {
+        assert!((yaw - want).abs() < 1e-5, "{yaws:?}");
+    }
+}
+
+#[test]
+fn smoothing_restarts_after_an_outage() {
+    let t0 = Instant::now();
+    let mut p = pipeline_with(
+        Config {
+            failsafe_ms: TIMEOUT.as_millis() as u64,
+            alpha: 0.5,
+            ..Config::default()
+        },
+        t0,
+    );
+    let mut yaw = 0.0;
+    for i in 0..30 {
+        yaw = p.on_datagram(&packet(1.0, f64::from(i)), t0).unwrap().yaw;
+    }
+    assert!(yaw > 0.99);
+    assert!(p.poll(t0 + TIMEOUT).is_some());
+    // Blended with the failsafe frame that was output, not the old average.
+    let out = p
+        .on_datagram(&packet(-1.0, 100.0), t0 + 2 * TIMEOUT)
+        .unwrap();
+    assert_eq!(out.yaw, -0.5);
+}
@@ -0,0 +1,172 @@
+use std::time::{Duration, Instant};
+
+use bci_receiver::filter::{
+    KalmanFilter1D, KalmanParams, RecoveryRamp, SlewLimiter, SlewLimits, Smoother,
+};
+use bci_receiver::ControlFrame;
+
+fn frame(yaw: f32, throttle: f32, ts: f64) -> ControlFrame {
+    ControlFrame {
+        yaw,
+        throttle,
+        ts,
+        safe: false,
+        ..ControlFrame::NEUTRAL
+    }
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-5
+}
+
+#[test]
+fn slew_limits_the_step_by_packet_time() {
+    let mut slew = SlewLimiter::new(SlewLimits {
+        yaw: Some(2.0),
+        throttle: None,
+    });
+    // The first frame only sets the time base.
+    assert_eq!(slew.apply(&frame(1.0, 1.0, 10.0)).yaw, 0.0);
+    let out = slew.apply(&frame(1.0, 1.0, 10.1));
+    assert!(close(out.yaw, 0.2), "{}", out.yaw);
+    // Throttle has no limit and follows immediately.
+    assert_eq!(out.throttle, 1.0);
+    let out = slew.apply(&frame(-1.0, 1.0, 10.2));
+    assert!(close(out.yaw, 0.0), "{}", out.yaw);
+    // Enough time to get anywhere.
+    assert_eq!(slew.apply(&frame(-1.0, 1.0, 20.0)).yaw, -1.0);
+}
+
+#[test]
+fn frames_released_together_still_advance() {
+    // The jitter buffer may release several frames in one tick; only their
+    // stamps decide how far the output moves.
+    let mut slew = SlewLimiter::new(SlewLimits {
+        yaw: Some(1.0),
+        throttle: Some(1.0),
+    });
+    slew.apply(&frame(0.0, -1.0, 0.0));
+    let mut out = ControlFrame::NEUTRAL;
+    for i in 1..=5 {
+        out = slew.apply(&frame(1.0, 1.0, f64::from(i) * 0.1));
+    }
+    assert!(close(out.yaw, 0.5), "{}", out.yaw);
+    assert!(close(out.throttle, -0.5), "{}", out.throttle);
+}
+
+#[test]
+fn out_of_order_stamps_do_not_move_the_output() {
+    let mut slew = SlewLimiter::new(SlewLimits {
+        yaw: Some(1.0),
+        throttle: None,
+    });
+    slew.apply(&frame(0.0, 0.0, 5.0));
+    assert_eq!(slew.apply(&frame(1.0, 0.0, 4.0)).yaw, 0.0);
+    assert!(close(slew.apply(&frame(1.0, 0.0, 5.5)).yaw, 0.5));
+}
+
+#[test]
+fn slew_reset_restarts_from_the_held_frame() {
+    let mut slew = SlewLimiter::new(SlewLimits {
+        yaw: Some(1.0),
+        throttle: Some(1.0),
+    });
+    slew.apply(&frame(0.0, 0.0, 0.0));
+    slew.apply(&frame(0.5, 0.5, 1.0));
+    let held = frame(0.0, -1.0, 0.0);
+    slew.reset_to(&held);
+    // A long gap before the next packet doesn't let it jump.
+    let out = slew.apply(&frame(1.0, 1.0, 100.0));
+    assert_eq!((out.yaw, out.throttle), (0.0, -1.0));
+    let out = slew.apply(&frame(1.0, 1.0, 100.25));
+    assert!(close(out.yaw, 0.25) && close(out.throttle, -0.75));
+}
+
+#[test]
+fn kalman_tracks_a_ramp_with_irregular_spacing() {
+    let mut kf = KalmanFilter1D::new(KalmanParams::default());
+    // Gaps from 5 ms to 250 ms, repeating, over about 8 s.
+    let gaps = [0.005, 0.25, 0.02, 0.1, 0.06, 0.17, 0.01];
+    let mut ts = 0.0;
+    let mut out = 0.0;
+    for i in 0..70 {
+        ts += gaps[i % gaps.len()];
+        out = kf.update((0.1 * ts) as f32, ts);
+    }
+    let truth = (0.1 * ts) as f32;
+    assert!((out - truth).abs() < 0.02, "{out} vs {truth}");
+    assert!((kf.velocity() - 0.1).abs() < 0.05, "{}", kf.velocity());
+}
+
+#[test]
+fn kalman_settles_on_a_constant_despite_bursty_timing() {
+    let mut kf = KalmanFilter1D::new(KalmanParams::default());
+    let mut ts = 0.0;
+    let mut out = 0.0;
+    for i in 0..60 {
+        // Bursts of near-simultaneous samples, then a long pause.
+        ts += if i % 4 == 0 { 0.3 } else { 1e-4 };
+        out = kf.update(0.4, ts);
+    }
+    assert!((out - 0.4).abs() < 1e-3, "{out}");
+}
+
+#[test]
+fn kalman_reset_to_blends_from_the_held_value() {
+    let mut kf = KalmanFilter1D::new(KalmanParams::default());
+    for i in 0..20 {
+        kf.update(0.8, f64::from(i) * 0.02);
+    }
+    kf.reset_to(0.0);
+    let first = kf.update(0.8, 100.0);
+    assert!(first > 0.0 && first < 0.8, "{first}");
+    assert_eq!(kf.velocity(), 0.0);
+    // The step briefly overshoots as the rate estimate catches up, then settles.
+    let mut out = first;
+    for i in 1..300 {
+        out = kf.update(0.8, 100.0 + f64::from(i) * 0.02);
+    }
+    assert!((out - 0.8).abs() < 0.01, "{out}");
+}
+
+#[test]
+fn recovery_ramp_blends_from_the_failsafe_frame_over_its_duration() {
+    let mut ramp = RecoveryRamp::new(Duration::from_millis(200), ControlFrame::NEUTRAL);
+    let t0 = Instant::now();
+    let ms = |n| t0 + Duration::from_millis(n);
+    let live = frame(1.0, 1.0, 0.0);
+    // Not started: live input passes straight through.
+    assert_eq!(ramp.apply(&live, t0), live);
+    ramp.start(t0);
+    let out = ramp.apply(&live, t0);
+    assert_eq!((out.yaw, out.throttle), (0.0, -1.0));
+    let out = ramp.apply(&live, ms(50));
+    assert!(close(out.yaw, 0.25) && close(out.throttle, -0.5));
+    let out = ramp.apply(&live, ms(100));
+    assert!(close(out.yaw, 0.5) && close(out.throttle, 0.0));
+    assert_eq!(ramp.apply(&live, ms(200)), live);
+    // Once finished it stays off until started again.
+    assert_eq!(ramp.apply(&live, ms(201)), live);
+}
+
+#[test]
+fn zero_duration_disables_the_ramp() {
+    let mut ramp = RecoveryRamp::new(Duration::ZERO, ControlFrame::NEUTRAL);
+    let now = Instant::now();
+    ramp.start(now);
+    let live = frame(1.0, 1.0, 0.0);
+    assert_eq!(ramp.apply(&live, now), live);
+}
+
+#[test]
+fn smoother_reset_forgets_the_old_average() {
+    let mut ema = Smoother::new(0.5);
+    for _ in 0..30 {
+        ema.apply(&frame(1.0, 1.0, 0.0));
+    }
+    ema.reset_to(&ControlFrame::NEUTRAL);
+    let out = ema.apply(&frame(-1.0, 1.0, 1.0));
+    assert_eq!((out.yaw, out.throttle), (-0.5, 0.0));
+    ema.reset();
+    assert_eq!(ema.apply(&frame(1.0, -1.0, 2.0)).yaw, 0.5);
+}
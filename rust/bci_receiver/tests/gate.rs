@@ -0,0 +1,78 @@
+use bci_receiver::{ConfidenceGate, ControlFrame, GateMode};
+
+const FRAME: ControlFrame = ControlFrame {
+    yaw: 0.8,
+    pitch: -0.4,
+    throttle: 0.5,
+    altitude: 0.3,
+    ..ControlFrame::NEUTRAL
+};
+
+fn gate(mode: GateMode) -> ConfidenceGate {
+    ConfidenceGate {
+        threshold: 0.5,
+        mode,
+    }
+}
+
+#[test]
+fn confident_or_unscored_frames_pass_untouched() {
+    let g = gate(GateMode::Zero);
+    assert_eq!(g.apply(Some(0.5), &FRAME, &ControlFrame::NEUTRAL), None);
+    assert_eq!(g.apply(Some(0.9), &FRAME, &ControlFrame::NEUTRAL), None);
+    assert_eq!(g.apply(None, &FRAME, &ControlFrame::NEUTRAL), None);
+}
+
+#[test]
+fn zero_mode_centers_sticks_and_keeps_altitude() {
+    let out = gate(GateMode::Zero)
+        .apply(Some(0.2), &FRAME, &ControlFrame::NEUTRAL)
+        .unwrap();
+    assert_eq!((out.yaw, out.pitch), (0.0, 0.0));
+    assert_eq!(out.throttle, ControlFrame::NEUTRAL.throttle);
+    assert_eq!(out.altitude, 0.3);
+}
+
+#[test]
+fn attenuate_mode_scales_towards_idle() {
+    let g = gate(GateMode::Attenuate);
+    let out = g.apply(Some(0.25), &FRAME, &ControlFrame::NEUTRAL).unwrap();
+    assert_eq!((out.yaw, out.pitch), (0.4, -0.2));
+    // Halfway from idle (-1) to 0.5.
+    assert_eq!(out.throttle, -0.25);
+    let out = g.apply(Some(-1.0), &FRAME, &ControlFrame::NEUTRAL).unwrap();
+    assert_eq!(out.yaw, 0.0);
+}
+
+#[test]
+fn pass_mode_keeps_the_frame_but_reports_it() {
+    assert_eq!(
+        gate(GateMode::Pass).apply(Some(0.1), &FRAME, &ControlFrame::NEUTRAL),
+        Some(FRAME)
+    );
+}
+
+#[test]
+fn nan_confidence_counts_as_uncertain() {
+    assert!(gate(GateMode::Zero)
+        .apply(Some(f32::NAN), &FRAME, &ControlFrame::NEUTRAL)
+        .is_some());
+}
+
+#[test]
+fn gated_axes_fall_back_to_the_failsafe_frame() {
+    let safe = ControlFrame {
+        throttle: -0.5,
+        yaw: 0.2,
+        ..ControlFrame::NEUTRAL
+    };
+    let out = gate(GateMode::Zero)
+        .apply(Some(0.2), &FRAME, &safe)
+        .unwrap();
+    assert_eq!((out.yaw, out.pitch, out.throttle), (0.2, 0.0, -0.5));
+    assert_eq!(out.altitude, 0.3);
+    let out = gate(GateMode::Attenuate)
+        .apply(Some(0.25), &FRAME, &safe)
+        .unwrap();
+    assert_eq!((out.yaw, out.throttle), (0.5, 0.0));
+}
@@ -0,0 +1,36 @@
+use std::time::{Duration, Instant};
+
+use bci_receiver::jitter::{JitterBuffer, JitterConfig};
+
+#[test]
+fn releases_in_ts_order_after_the_delay() {
+    let mut buf = JitterBuffer::new(JitterConfig {
+        depth: 3,
+        delay_ms: 40,
+    });
+    let t0 = Instant::now();
+    let ms = |n| t0 + Duration::from_millis(n);
+    buf.push(2.0, "b", ms(0)).unwrap();
+    buf.push(1.0, "a", ms(5)).unwrap();
+    assert_eq!(buf.pop_due(ms(30)), None);
+    // "a" arrived last but is oldest, so it leads once its own delay is up.
+    assert_eq!(buf.pop_due(ms(45)), Some("a"));
+    assert_eq!(buf.pop_due(ms(45)), Some("b"));
+    assert_eq!(buf.pop_due(ms(45)), None);
+    // Older than what already left: dropped as late.
+    assert_eq!(buf.push(1.5, "late", ms(50)), Err("late"));
+}
+
+#[test]
+fn overfull_buffer_releases_early() {
+    let mut buf = JitterBuffer::new(JitterConfig {
+        depth: 2,
+        delay_ms: 40,
+    });
+    let now = Instant::now();
+    for (ts, item) in [(3.0, "c"), (1.0, "a"), (2.0, "b")] {
+        buf.push(ts, item, now).unwrap();
+    }
+    assert_eq!(buf.pop_due(now), Some("a"));
+    assert_eq!(buf.pop_due(now), None);
+}
@@ -0,0 +1,283 @@
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bci_receiver::pipeline::Pipeline;
+use bci_receiver::{receiver, Config, ControlFrame, ControlSink};
+
+/// Keeps every frame the output stage pushes.
+#[derive(Debug, Clone, Default)]
+struct CollectSink(Arc<Mutex<Vec<ControlFrame>>>);
+
+impl CollectSink {
+    /// Waits until a frame carrying `ts` has been emitted and returns it.
+    fn wait_for(&self, ts: f64) -> ControlFrame {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        loop {
+            if let Some(f) = self.0.lock().unwrap().iter().find(|f| f.ts == ts) {
+                return *f;
+            }
+            assert!(Instant::now() < deadline, "no frame with ts {ts} emitted");
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
+
+impl ControlSink for CollectSink {
+    fn send(&mut self, frame: &ControlFrame) -> io::Result<()> {
+        self.0.lock().unwrap().push(*frame);
+        Ok(())
+    }
+}
+
+/// Runs the receiver on an ephemeral port of `bind_ip` while `test` talks to it
+/// through a connected client socket.
+fn with_receiver(bind_ip: &str, test: impl FnOnce(&UdpSocket, &CollectSink)) {
+    let sock = UdpSocket::bind((bind_ip, 0)).unwrap();
+    let client = UdpSocket::bind((bind_ip, 0)).unwrap();
+    client.connect(sock.local_addr().unwrap()).unwrap();
+    with_socket(sock, client, test);
+}
+
+fn with_socket(sock: UdpSocket, client: UdpSocket, test: impl FnOnce(&UdpSocket, &CollectSink)) {
+    with_config(sock, client, test_config(), test);
+}
+
+fn test_config() -> Config {
+    Config {
+        failsafe_ms: 1000,
+        output_hz: 200.0,
+        ..Config::default()
+    }
+}
+
+fn with_config(
+    sock: UdpSocket,
+    client: UdpSocket,
+    cfg: Config,
+    test: impl FnOnce(&UdpSocket, &CollectSink),
+) {
+    let shutdown = AtomicBool::new(false);
+    let sink = CollectSink::default();
+    thread::scope(|s| {
+        let rx = s.spawn(|| receiver::run(&sock, &mut sink.clone(), &cfg, &shutdown));
+        test(&client, &sink);
+        shutdown.store(true, Ordering::Relaxed);
+        // Wake the blocking read instead of waiting out the timeout.
+        client.send(b"").unwrap();
+        rx.join().unwrap().unwrap();
+    });
+}
+
+#[test]
+fn decodes_datagrams_end_to_end() {
+    with_receiver("127.0.0.1", |client, sink| {
+        client
+            .send(br#"{"yaw":0.5,"altitude":0.25,"pitch":-0.5,"throttle":0.75,"ts":1.0}"#)
+            .unwrap();
+        let f = sink.wait_for(1.0);
+        assert_eq!(
+            (f.yaw, f.altitude, f.pitch, f.roll, f.throttle),
+            (0.5, 0.25, -0.5, 0.0, 0.75)
+        );
+    });
+}
+
+#[test]
+fn speed_is_used_when_throttle_is_missing() {
+    with_receiver("127.0.0.1", |client, sink| {
+        client
+            .send(br#"{"yaw":0,"altitude":0,"speed":0.75,"ts":1.0}"#)
+            .unwrap();
+        assert_eq!(sink.wait_for(1.0).throttle, 0.5);
+
+        client.send(br#"{"yaw":0,"altitude":0,"ts":2.0}"#).unwrap();
+        assert_eq!(sink.wait_for(2.0).throttle, -1.0);
+    });
+}
+
+#[test]
+fn out_of_range_axes_are_clamped() {
+    with_receiver("127.0.0.1", |client, sink| {
+        client
+            .send(br#"{"yaw":3.0,"altitude":-2.0,"pitch":1.5,"throttle":9,"ts":1.0}"#)
+            .unwrap();
+        let f = sink.wait_for(1.0);
+        assert_eq!(
+            (f.yaw, f.altitude, f.pitch, f.throttle),
+            (1.0, -1.0, 1.0, 1.0)
+        );
+    });
+}
+
+#[test]
+fn bad_datagrams_are_skipped() {
+    with_receiver("127.0.0.1", |client, sink| {
+        client.send(b"not json").unwrap();
+        client
+            .send(br#"{"yaw":"left","altitude":0,"ts":1.0}"#)
+            .unwrap();
+        client
+            .send(br#"{"yaw":0.25,"altitude":0,"ts":2.0}"#)
+            .unwrap();
+        assert_eq!(sink.wait_for(2.0).yaw, 0.25);
+        assert!(sink.0.lock().unwrap().iter().all(|f| f.ts != 1.0));
+    });
+}
+
+/// `json` padded with trailing spaces to exactly `len` bytes.
+fn padded(json: &str, len: usize) -> Vec<u8> {
+    let mut out = json.as_bytes().to_vec();
+    out.resize(len, b' ');
+    out
+}
+
+#[test]
+fn oversized_datagrams_are_dropped() {
+    with_receiver("127.0.0.1", |client, sink| {
+        let limit = Config::default().recv_buffer;
+        client
+            .send(&padded(r#"{"yaw":0.5,"altitude":0,"ts":1.0}"#, limit + 1))
+            .unwrap();
+        client
+            .send(&padded(r#"{"yaw":0.25,"altitude":0,"ts":2.0}"#, limit))
+            .unwrap();
+        assert_eq!(sink.wait_for(2.0).yaw, 0.25);
+        assert!(sink.0.lock().unwrap().iter().all(|f| f.ts != 1.0));
+    });
+}
+
+#[test]
+fn ipv6_loopback_round_trip() {
+    let addr: SocketAddr = "[::1]:0".parse().unwrap();
+    let sock = receiver::bind(addr, false).unwrap();
+    let client = UdpSocket::bind("[::1]:0").unwrap();
+    client.connect(sock.local_addr().unwrap()).unwrap();
+    with_socket(sock, client, |client, sink| {
+        client
+            .send(br#"{"yaw":-0.5,"altitude":0,"ts":1.0}"#)
+            .unwrap();
+        assert_eq!(sink.wait_for(1.0).yaw, -0.5);
+    });
+}
+
+#[test]
+fn dual_stack_accepts_ipv4_senders() {
+    let addr: SocketAddr = "[::]:0".parse().unwrap();
+    let sock = receiver::bind(addr, true).unwrap();
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client
+        .connect(("127.0.0.1", sock.local_addr().unwrap().port()))
+        .unwrap();
+    with_socket(sock, client, |client, sink| {
+        client
+            .send(br#"{"yaw":0.5,"altitude":0,"ts":1.0}"#)
+            .unwrap();
+        assert_eq!(sink.wait_for(1.0).yaw, 0.5);
+    });
+}
+
+#[test]
+fn unknown_targets_are_dropped() {
+    with_receiver("127.0.0.1", |client, sink| {
+        client
+            .send(br#"{"yaw":0.5,"altitude":0,"target":3,"ts":1.0}"#)
+            .unwrap();
+        client
+            .send(br#"{"yaw":0.25,"altitude":0,"ts":2.0}"#)
+            .unwrap();
+        assert_eq!(sink.wait_for(2.0).yaw, 0.25);
+        assert!(sink.0.lock().unwrap().iter().all(|f| f.ts != 1.0));
+    });
+}
+
+#[test]
+fn estop_holds_failsafe_without_arming() {
+    with_receiver("127.0.0.1", |client, sink| {
+        client
+            .send(br#"{"yaw":0.5,"altitude":0,"ts":1.0}"#)
+            .unwrap();
+        assert_eq!(sink.wait_for(1.0).yaw, 0.5);
+        // Version 2 without a throttle: the axes are rejected, the estop is not.
+        client
+            .send(br#"{"v":2,"yaw":0.5,"altitude":0,"event":"estop","ts":2.0}"#)
+            .unwrap();
+        client
+            .send(br#"{"yaw":0.5,"altitude":0,"ts":3.0}"#)
+            .unwrap();
+        let f = sink.wait_for(3.0);
+        assert_eq!((f.yaw, f.throttle), (0.0, -1.0));
+    });
+}
+
+#[test]
+fn estop_stays_latched_while_arm_is_held() {
+    let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client.connect(sock.local_addr().unwrap()).unwrap();
+    let cfg = Config {
+        arm_packets: Some(2),
+        ..test_config()
+    };
+    with_config(sock, client, cfg, |client, sink| {
+        let send = |json: &str| client.send(json.as_bytes()).unwrap();
+        send(r#"{"yaw":0.5,"altitude":0,"arm":true,"ts":1.0}"#);
+        send(r#"{"yaw":0.5,"altitude":0,"arm":true,"ts":2.0}"#);
+        assert_eq!(sink.wait_for(2.0).yaw, 0.5);
+        send(r#"{"yaw":0.5,"altitude":0,"arm":true,"event":"estop","ts":3.0}"#);
+        for ts in 4..10 {
+            send(&format!(
+                r#"{{"yaw":0.5,"altitude":0,"arm":true,"ts":{ts}.0}}"#
+            ));
+        }
+        assert_eq!(sink.wait_for(9.0).yaw, 0.0);
+
+        // Releasing arm and arming again clears the stop.
+        send(r#"{"yaw":0.5,"altitude":0,"arm":false,"ts":10.0}"#);
+        send(r#"{"yaw":0.5,"altitude":0,"arm":true,"ts":11.0}"#);
+        send(r#"{"yaw":0.5,"altitude":0,"arm":true,"ts":12.0}"#);
+        assert_eq!(sink.wait_for(12.0).yaw, 0.5);
+    });
+}
+
+#[test]
+fn packets_from_other_senders_are_dropped() {
+    // The whole of 127/8 is loopback on Linux, so a second address is free.
+    let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let client = UdpSocket::bind("127.0.0.2:0").unwrap();
+    client.connect(sock.local_addr().unwrap()).unwrap();
+    let foreign = UdpSocket::bind("127.0.0.1:0").unwrap();
+    foreign.connect(sock.local_addr().unwrap()).unwrap();
+    let cfg = Config {
+        source: Some("127.0.0.2".parse().unwrap()),
+        ..test_config()
+    };
+    with_config(sock, client, cfg, |client, sink| {
+        foreign
+            .send(br#"{"yaw":0.9,"altitude":0,"ts":1.0}"#)
+            .unwrap();
+        client
+            .send(br#"{"yaw":0.25,"altitude":0,"ts":2.0}"#)
+            .unwrap();
+        assert_eq!(sink.wait_for(2.0).yaw, 0.25);
+        assert!(sink.0.lock().unwrap().iter().all(|f| f.ts != 1.0));
+    });
+}
+
+#[test]
+fn dropped_senders_are_counted() {
+    let cfg = Config {
+        source: Some("192.0.2.1".parse().unwrap()),
+        ..test_config()
+    };
+    let mut pipeline = Pipeline::new(&cfg, Instant::now()).unwrap();
+    assert!(pipeline.accept_source("192.0.2.1:5000".parse().unwrap()));
+    // A dual-stack socket reports the allowed sender as v4-mapped.
+    assert!(pipeline.accept_source("[::ffff:192.0.2.1]:5000".parse().unwrap()));
+    assert!(!pipeline.accept_source("192.0.2.2:5000".parse().unwrap()));
+    assert!(!pipeline.accept_source("127.0.0.1:5000".parse().unwrap()));
+    assert_eq!(pipeline.stats().snapshot().foreign, 2);
+}
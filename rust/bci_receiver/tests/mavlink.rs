@@ -0,0 +1,161 @@
+use std::net::UdpSocket;
+use std::time::Duration;
+
+use bci_receiver::config::Route;
+use bci_receiver::sink::MavlinkSink;
+use bci_receiver::{ControlFrame, ControlSink};
+
+const MSG_HEARTBEAT: u32 = 0;
+const MSG_MANUAL_CONTROL: u32 = 69;
+const MSG_SET_POSITION_TARGET_LOCAL_NED: u32 = 84;
+
+/// A local socket standing in for the autopilot.
+fn autopilot() -> UdpSocket {
+    let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sock.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    sock
+}
+
+/// Reads packets until one with message id `id` arrives.
+fn recv_msg(sock: &UdpSocket, id: u32) -> Vec<u8> {
+    let mut buf = [0u8; 280];
+    loop {
+        let len = sock.recv(&mut buf).unwrap();
+        let pkt = &buf[..len];
+        assert_eq!(pkt[0], 0xFD, "not a MAVLink 2 packet");
+        if u32::from_le_bytes([pkt[7], pkt[8], pkt[9], 0]) == id {
+            return pkt.to_vec();
+        }
+    }
+}
+
+/// Payload of a MAVLink 2 packet, between the 10-byte header and the CRC.
+fn payload(pkt: &[u8]) -> &[u8] {
+    &pkt[10..pkt.len() - 2]
+}
+
+/// Bit-by-bit CRC-16/MCRF4XX over `bytes` and the message's CRC_EXTRA.
+fn x25(bytes: &[u8], extra: u8) -> [u8; 2] {
+    let mut crc: u16 = 0xFFFF;
+    for &b in bytes.iter().chain([extra].iter()) {
+        crc ^= u16::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0x8408
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc.to_le_bytes()
+}
+
+#[test]
+fn manual_control_matches_golden_bytes() {
+    let ap = autopilot();
+    let mut sink = MavlinkSink::new(ap.local_addr().unwrap()).unwrap();
+    let frame = ControlFrame {
+        pitch: 0.5,
+        roll: -0.25,
+        throttle: 0.0,
+        yaw: 1.0,
+        ..ControlFrame::NEUTRAL
+    };
+    sink.send(&frame).unwrap();
+    // The first send also introduces the sender as a GCS.
+    #[rustfmt::skip]
+    let heartbeat = [
+        0xFD, 9, 0, 0, 0, 255, 190, 0, 0, 0, // header, seq 0, sysid 255, compid 190
+        0, 0, 0, 0, 6, 8, 0, 4, 3, // custom_mode, GCS, invalid autopilot, active, v3
+        0x3D, 0x48, // CRC with extra 50
+    ];
+    assert_eq!(recv_msg(&ap, MSG_HEARTBEAT), heartbeat);
+    #[rustfmt::skip]
+    let manual_control = [
+        0xFD, 11, 0, 0, 1, 255, 190, 69, 0, 0, // header, seq 1, msg 69
+        0xF4, 0x01, // x = pitch * 1000
+        0x06, 0xFF, // y = roll * 1000
+        0xF4, 0x01, // z: throttle -1..1 onto 0..1000
+        0xE8, 0x03, // r = yaw * 1000
+        0, 0, 1, // buttons, target system
+        0x09, 0x36, // CRC with extra 243
+    ];
+    assert_eq!(recv_msg(&ap, MSG_MANUAL_CONTROL), manual_control);
+}
+
+#[test]
+fn manual_control_scales_full_deflection() {
+    let ap = autopilot();
+    let mut sink = MavlinkSink::new(ap.local_addr().unwrap()).unwrap();
+    let axis = |p: &[u8], i: usize| i16::from_le_bytes([p[i], p[i + 1]]);
+    for (v, stick, thrust) in [(-1.0, -1000, 0), (1.0, 1000, 1000), (7.0, 1000, 1000)] {
+        let frame = ControlFrame {
+            yaw: v,
+            pitch: v,
+            roll: v,
+            throttle: v,
+            ..ControlFrame::NEUTRAL
+        };
+        sink.send(&frame).unwrap();
+        let pkt = recv_msg(&ap, MSG_MANUAL_CONTROL);
+        let p = payload(&pkt);
+        assert_eq!([axis(p, 0), axis(p, 2), axis(p, 6)], [stick; 3], "{v}");
+        assert_eq!(axis(p, 4), thrust, "{v}");
+    }
+}
+
+#[test]
+fn routed_vehicle_is_addressed_by_its_target_id() {
+    let ap = autopilot();
+    let route = Route {
+        target: 3,
+        mavlink: ap.local_addr().unwrap(),
+    };
+    let mut sink = route.sink().unwrap();
+    let frame = ControlFrame {
+        yaw: 0.5,
+        target: Some(3),
+        ..ControlFrame::NEUTRAL
+    };
+    sink.send(&frame).unwrap();
+    let pkt = recv_msg(&ap, MSG_MANUAL_CONTROL);
+    let p = payload(&pkt);
+    assert_eq!(p.len(), 11);
+    assert_eq!(i16::from_le_bytes([p[6], p[7]]), 500);
+    // target system
+    assert_eq!(p[10], 3);
+}
+
+#[test]
+fn altitude_setpoint_matches_golden_layout() {
+    let ap = autopilot();
+    let mut sink = MavlinkSink::new(ap.local_addr().unwrap())
+        .unwrap()
+        .with_target_system(7);
+    let frame = ControlFrame {
+        altitude_m: Some(12.5),
+        ..ControlFrame::NEUTRAL
+    };
+    sink.send(&frame).unwrap();
+    let pkt = recv_msg(&ap, MSG_SET_POSITION_TARGET_LOCAL_NED);
+    // Only time_boot_ms depends on the clock; it is a few ms at most here.
+    let boot_ms = u32::from_le_bytes([pkt[10], pkt[11], pkt[12], pkt[13]]);
+    assert!(boot_ms < 1000, "time_boot_ms {boot_ms}");
+
+    let mut expected = vec![0xFD, 53, 0, 0, 2, 255, 190, 84, 0, 0]; // seq 2, msg 84
+    expected.extend_from_slice(&boot_ms.to_le_bytes());
+    expected.extend_from_slice(&[0; 8]); // x, y
+    expected.extend_from_slice(&[0x00, 0x00, 0x48, 0xC1]); // z = -12.5 (NED, down)
+    expected.extend_from_slice(&[0; 32]); // velocities, accelerations, yaw, yaw rate
+    expected.extend_from_slice(&[0xFB, 0x0D]); // type_mask: everything but z ignored
+    expected.extend_from_slice(&[7, 1, 1]); // target system, autopilot component, LOCAL_NED
+    let crc = x25(&expected[1..], 143);
+    expected.extend_from_slice(&crc);
+    assert_eq!(pkt, expected);
+}
+
+#[test]
+fn crc_helper_agrees_with_the_golden_heartbeat() {
+    let header_and_payload = [9, 0, 0, 0, 255, 190, 0, 0, 0, 0, 0, 0, 0, 6, 8, 0, 4, 3];
+    assert_eq!(x25(&header_and_payload, 50), [0x3D, 0x48]);
+}
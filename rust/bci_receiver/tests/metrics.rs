@@ -0,0 +1,97 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+
+use bci_receiver::metrics::MetricsServer;
+use bci_receiver::Stats;
+
+/// Scrapes `server` once and returns the response body.
+fn scrape(server: &MetricsServer) -> String {
+    let mut conn = TcpStream::connect(server.local_addr()).unwrap();
+    conn.write_all(b"GET /metrics HTTP/1.0\r\n\r\n").unwrap();
+    let mut resp = String::new();
+    conn.read_to_string(&mut resp).unwrap();
+    let (head, body) = resp.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.0 200 OK"), "{head}");
+    assert!(head.contains("Content-Type: text/plain; version=0.0.4"));
+    body.to_string()
+}
+
+/// The value of the sample line `name` (including any labels).
+fn sample(body: &str, name: &str) -> f64 {
+    body.lines()
+        .find_map(|l| l.strip_prefix(name)?.strip_prefix(' '))
+        .unwrap_or_else(|| panic!("no sample {name} in\n{body}"))
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn renders_counters_with_help_and_type() {
+    let stats = Arc::new(Stats::default());
+    for _ in 0..3 {
+        stats.record_packet(None);
+    }
+    stats.record_json_error();
+    stats.record_foreign();
+    stats.record_failsafe();
+    let server = MetricsServer::start("127.0.0.1:0".parse().unwrap(), Arc::clone(&stats)).unwrap();
+    let body = scrape(&server);
+
+    assert_eq!(sample(&body, "bci_packets_received_total"), 3.0);
+    assert_eq!(sample(&body, "bci_parse_errors_total{kind=\"json\"}"), 1.0);
+    assert_eq!(
+        sample(&body, "bci_rejected_packets_total{reason=\"source\"}"),
+        1.0
+    );
+    assert_eq!(sample(&body, "bci_failsafe_activations_total"), 1.0);
+    assert_eq!(sample(&body, "bci_failsafe_active"), 1.0);
+    // Every metric family is introduced by its HELP and TYPE lines.
+    let lines: Vec<&str> = body.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(rest) = line.strip_prefix("# HELP ") {
+            let name = rest.split(' ').next().unwrap();
+            let kind = lines[i + 1]
+                .strip_prefix(&format!("# TYPE {name} "))
+                .unwrap();
+            assert!(["counter", "gauge", "histogram"].contains(&kind), "{kind}");
+            if kind == "counter" {
+                assert!(name.ends_with("_total"), "{name}");
+            }
+        } else if !line.starts_with("# TYPE ") {
+            assert!(line.starts_with("bci_"), "{line}");
+        }
+    }
+    assert!(body.contains("# TYPE bci_packets_received_total counter\n"));
+    assert!(body.contains("# TYPE bci_output_rate_hz gauge\n"));
+}
+
+#[test]
+fn latency_histogram_is_cumulative_and_ends_in_inf() {
+    let stats = Arc::new(Stats::default());
+    for seconds in [0.0005, 0.003, 0.003, 0.2, 30.0] {
+        stats.record_latency(seconds);
+    }
+    let server = MetricsServer::start("127.0.0.1:0".parse().unwrap(), Arc::clone(&stats)).unwrap();
+    let body = scrape(&server);
+
+    assert!(body.contains("# TYPE bci_packet_latency_seconds histogram\n"));
+    let buckets: Vec<(&str, u64)> = body
+        .lines()
+        .filter_map(|l| l.strip_prefix("bci_packet_latency_seconds_bucket{le=\""))
+        .map(|l| {
+            let (le, n) = l.split_once("\"} ").unwrap();
+            (le, n.parse().unwrap())
+        })
+        .collect();
+    assert_eq!(buckets.last(), Some(&("+Inf", 5)));
+    assert!(buckets.windows(2).all(|w| w[0].1 <= w[1].1), "{buckets:?}");
+    let at = |le: &str| buckets.iter().find(|b| b.0 == le).unwrap().1;
+    assert_eq!(at("0.001"), 1);
+    assert_eq!(at("0.005"), 3);
+    assert_eq!(at("0.25"), 4);
+    assert_eq!(at("2.5"), 4);
+    assert_eq!(sample(&body, "bci_packet_latency_seconds_count"), 5.0);
+    let sum = sample(&body, "bci_packet_latency_seconds_sum");
+    assert!((sum - 30.2065).abs() < 1e-5, "{sum}");
+}
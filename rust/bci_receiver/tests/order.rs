@@ -0,0 +1,38 @@
+use bci_receiver::order::OrderGuard;
+
+#[test]
+fn seq_wraps_around_u32_max() {
+    let mut guard = OrderGuard::default();
+    assert!(guard.accept(Some(u32::MAX - 1), 1.0));
+    assert!(guard.accept(Some(u32::MAX), 2.0));
+    assert!(guard.accept(Some(0), 3.0));
+    assert!(guard.accept(Some(1), 4.0));
+    // The pre-wrap packet is now the older one.
+    assert!(!guard.accept(Some(u32::MAX), 5.0));
+}
+
+#[test]
+fn duplicate_and_older_seq_are_dropped() {
+    let mut guard = OrderGuard::default();
+    assert!(guard.accept(Some(10), 1.0));
+    assert!(!guard.accept(Some(10), 1.0));
+    assert!(!guard.accept(Some(9), 2.0));
+    assert!(guard.accept(Some(12), 0.5));
+}
+
+#[test]
+fn ts_orders_packets_without_seq() {
+    let mut guard = OrderGuard::default();
+    assert!(guard.accept(None, 1.0));
+    assert!(!guard.accept(None, 1.0));
+    assert!(!guard.accept(None, 0.5));
+    assert!(guard.accept(None, 1.5));
+}
+
+#[test]
+fn reset_accepts_a_restarted_sender() {
+    let mut guard = OrderGuard::default();
+    assert!(guard.accept(Some(100), 100.0));
+    guard.reset();
+    assert!(guard.accept(Some(0), 1.0));
+}
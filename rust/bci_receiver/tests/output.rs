@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+use bci_receiver::output::{Extrapolation, Sample};
+use bci_receiver::ControlFrame;
+
+fn live(yaw: f32, ts: f64) -> ControlFrame {
+    ControlFrame {
+        yaw,
+        ts,
+        safe: false,
+        ..ControlFrame::NEUTRAL
+    }
+}
+
+/// `latest` published 60 ms ago, after `prev`.
+fn predict(prev: ControlFrame, latest: ControlFrame) -> ControlFrame {
+    let now = Instant::now();
+    let sample = Sample {
+        prev: Some(prev),
+        latest,
+        published_at: now - Duration::from_millis(60),
+    };
+    Extrapolation::default().predict(&sample, now)
+}
+
+#[test]
+fn extrapolates_live_trend() {
+    let f = predict(live(0.4, 1.0), live(0.5, 1.02));
+    assert!(f.yaw > 0.5);
+}
+
+#[test]
+fn failsafe_frames_are_never_extrapolated() {
+    // Disarm from yaw 0.8: the failsafe frame carries the packet's ts.
+    let safe = ControlFrame {
+        ts: 1.02,
+        ..ControlFrame::NEUTRAL
+    };
+    assert_eq!(predict(live(0.8, 1.0), safe), safe);
+
+    // Nor is the first live frame after one.
+    let back = live(0.3, 1.04);
+    assert_eq!(predict(safe, back), back);
+}
+
+#[test]
+fn height_setpoints_hold_their_altitude() {
+    let climbing = |altitude: f32, ts: f64| ControlFrame {
+        altitude,
+        altitude_m: Some(10.0 + 10.0 * altitude),
+        ..live(0.0, ts)
+    };
+    let (prev, latest) = (climbing(0.2, 1.0), climbing(0.4, 1.02));
+    let f = predict(prev, latest);
+    assert_eq!(f.altitude, latest.altitude);
+    assert_eq!(f.altitude_m, latest.altitude_m);
+
+    // Without a setpoint the altitude axis follows its trend like the others.
+    let f = predict(
+        ControlFrame {
+            altitude_m: None,
+            ..prev
+        },
+        ControlFrame {
+            altitude_m: None,
+            ..latest
+        },
+    );
+    assert!(f.altitude > 0.4);
+    assert_eq!(f.altitude_m, None);
+}
@@ -0,0 +1,65 @@
+use std::fs;
+use std::path::PathBuf;
+
+use bci_receiver::parse_msg;
+use bci_receiver::record::Recorder;
+use serde_json::Value;
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("bci-{}-{name}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn records_one_json_object_per_line() {
+    let path = temp_path("record.jsonl");
+    let mut rec = Recorder::create(&path).unwrap();
+    let msg = parse_msg(br#"{"yaw":0.5,"altitude":-0.25,"ts":12.5,"seq":7}"#).unwrap();
+    rec.record(&msg, 1700000000.25);
+    rec.record(
+        &parse_msg(br#"{"yaw":0,"altitude":0,"ts":13}"#).unwrap(),
+        1700000001.0,
+    );
+    rec.finish().unwrap();
+
+    let text = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let lines: Vec<Value> = text
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    // The arrival time sits next to the message fields, not in a nested object.
+    assert_eq!(lines[0]["recv_ts"], 1700000000.25);
+    assert_eq!(lines[0]["yaw"], 0.5);
+    assert_eq!(lines[0]["altitude"], -0.25);
+    assert_eq!(lines[0]["ts"], 12.5);
+    assert_eq!(lines[0]["seq"], 7);
+    // Fields the sender left out stay out.
+    let keys: Vec<&str> = lines[1]
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(|k| k.as_str())
+        .collect();
+    assert_eq!(keys, ["altitude", "recv_ts", "ts", "yaw"]);
+}
+
+#[test]
+fn appends_to_an_existing_recording() {
+    let path = temp_path("append.jsonl");
+    for ts in [1.0, 2.0] {
+        let mut rec = Recorder::create(&path).unwrap();
+        let msg = parse_msg(format!(r#"{{"yaw":0,"altitude":0,"ts":{ts}}}"#).as_bytes()).unwrap();
+        rec.record(&msg, ts);
+        rec.finish().unwrap();
+    }
+    let text = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(text.lines().count(), 2);
+    // Every recorded line is a message the receiver accepts as is.
+    for line in text.lines() {
+        parse_msg(line.as_bytes()).unwrap();
+    }
+}
@@ -0,0 +1,94 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+use bci_receiver::pipeline::Pipeline;
+use bci_receiver::replay::replay;
+use bci_receiver::{Config, ControlFrame, ControlSink};
+
+#[derive(Debug, Default)]
+struct VecSink(Vec<ControlFrame>);
+
+impl ControlSink for VecSink {
+    fn send(&mut self, frame: &ControlFrame) -> io::Result<()> {
+        self.0.push(*frame);
+        Ok(())
+    }
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("bci-{}-{name}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn test_config() -> Config {
+    Config {
+        output_hz: 200.0,
+        ..Config::default()
+    }
+}
+
+#[test]
+fn replays_what_was_recorded() {
+    let path = temp_path("roundtrip.jsonl");
+    let recording = Config {
+        record: Some(path.clone()),
+        ..test_config()
+    };
+    let mut pipeline = Pipeline::new(&recording, Instant::now())
+        .unwrap()
+        .without_latency();
+    let sent = [(1.0, 0.1), (1.04, 0.2), (1.08, -0.3), (1.12, 0.4)];
+    for (ts, yaw) in sent {
+        let msg = format!(r#"{{"yaw":{yaw},"altitude":0.5,"ts":{ts}}}"#);
+        assert!(pipeline
+            .on_datagram(msg.as_bytes(), Instant::now())
+            .is_some());
+    }
+    pipeline.finish().unwrap();
+
+    let mut sink = VecSink::default();
+    let start = Instant::now();
+    replay(
+        &path,
+        1.0,
+        &mut sink,
+        &test_config(),
+        &AtomicBool::new(false),
+    )
+    .unwrap();
+    let elapsed = start.elapsed();
+    fs::remove_file(&path).unwrap();
+
+    // The output stage repeats frames at its own rate; keep each packet's first one.
+    let mut live: Vec<ControlFrame> = sink.0.into_iter().filter(|f| !f.safe).collect();
+    live.dedup_by(|a, b| a.ts == b.ts);
+    let replayed: Vec<(f64, f32)> = live.iter().map(|f| (f.ts, f.yaw)).collect();
+    assert_eq!(replayed, sent);
+    assert!(live.iter().all(|f| f.altitude == 0.5));
+    // Lines are spaced by their recorded ts, 120 ms from first to last.
+    assert!(elapsed >= Duration::from_millis(120), "{elapsed:?}");
+}
+
+#[test]
+fn replay_refuses_to_record() {
+    let path = temp_path("rerecord.jsonl");
+    fs::write(&path, "").unwrap();
+    let cfg = Config {
+        record: Some(path.clone()),
+        ..test_config()
+    };
+    let err = replay(
+        &path,
+        1.0,
+        &mut VecSink::default(),
+        &cfg,
+        &AtomicBool::new(false),
+    )
+    .unwrap_err();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,58 @@
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use bci_receiver::sink::RouterSink;
+use bci_receiver::{ControlFrame, ControlSink};
+
+type Log = Rc<RefCell<Vec<(&'static str, f32)>>>;
+
+/// Records the yaw of every frame it is sent, tagged with its name.
+struct Tap(&'static str, Log);
+
+impl ControlSink for Tap {
+    fn send(&mut self, f: &ControlFrame) -> io::Result<()> {
+        self.1.borrow_mut().push((self.0, f.yaw));
+        Ok(())
+    }
+}
+
+fn router() -> (RouterSink<Tap>, Log) {
+    let log = Log::default();
+    let router = RouterSink::new(Tap("default", log.clone()))
+        .with_route(1, Tap("one", log.clone()))
+        .with_route(2, Tap("two", log.clone()));
+    (router, log)
+}
+
+fn live(yaw: f32, target: Option<u8>) -> ControlFrame {
+    ControlFrame {
+        yaw,
+        target,
+        safe: false,
+        ..ControlFrame::NEUTRAL
+    }
+}
+
+#[test]
+fn live_frames_reach_only_the_addressed_sink() {
+    let (mut router, log) = router();
+    router.send(&live(0.1, Some(2))).unwrap();
+    router.send(&live(0.2, None)).unwrap();
+    router.send(&live(0.3, Some(1))).unwrap();
+    // The others hear nothing, so an unselected aircraft isn't throttled down.
+    assert_eq!(
+        *log.borrow(),
+        [("two", 0.1), ("default", 0.2), ("one", 0.3)]
+    );
+}
+
+#[test]
+fn failsafe_frames_reach_every_sink() {
+    let (mut router, log) = router();
+    router.send(&ControlFrame::NEUTRAL).unwrap();
+    assert_eq!(
+        *log.borrow(),
+        [("default", 0.0), ("one", 0.0), ("two", 0.0)]
+    );
+}
@@ -0,0 +1,29 @@
+use std::time::{Duration, Instant};
+
+use bci_receiver::pipeline::Pipeline;
+use bci_receiver::{Config, Stats};
+
+#[test]
+fn absurd_latencies_do_not_wrap_the_mean() {
+    let stats = Stats::default();
+    for _ in 0..100 {
+        stats.record_latency(1e12);
+    }
+    let latency = stats.snapshot().latency.unwrap();
+    assert_eq!(latency.samples, 100);
+    assert_eq!(latency.mean, Duration::from_secs(3600));
+    assert_eq!(latency.max, Duration::from_secs(3600));
+}
+
+#[test]
+fn missing_required_fields_count_as_invalid() {
+    let mut pipeline = Pipeline::new(&Config::default(), Instant::now())
+        .unwrap()
+        .without_latency();
+    // Version 2 requires an explicit throttle.
+    let msg = br#"{"v":2,"yaw":0,"altitude":0,"ts":1}"#;
+    assert!(pipeline.on_datagram(msg, Instant::now()).is_none());
+    let snap = pipeline.stats().snapshot();
+    assert_eq!(snap.invalid, 1);
+    assert_eq!(snap.json_errors, 0);
+}
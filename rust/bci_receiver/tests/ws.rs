@@ -0,0 +1,96 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use bci_receiver::sink::WebSocketSink;
+use bci_receiver::{ControlFrame, ControlSink};
+
+/// The sample handshake from RFC 6455 §1.3.
+const KEY: &str = "dGhlIHNhbXBsZSBub25jZQ==";
+const ACCEPT: &str = "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=";
+
+fn sink() -> WebSocketSink {
+    WebSocketSink::bind("127.0.0.1:0".parse().unwrap()).unwrap()
+}
+
+/// Connects and upgrades, returning the socket and the server's response head.
+fn connect(sink: &WebSocketSink) -> (TcpStream, String) {
+    let mut conn = TcpStream::connect(sink.local_addr()).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    write!(
+        conn,
+        "GET / HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+         Connection: Upgrade\r\nSec-WebSocket-Key: {KEY}\r\n\
+         Sec-WebSocket-Version: 13\r\n\r\n"
+    )
+    .unwrap();
+    let mut head = Vec::new();
+    let mut byte = [0u8];
+    while !head.ends_with(b"\r\n\r\n") {
+        conn.read_exact(&mut byte).unwrap();
+        head.push(byte[0]);
+    }
+    (conn, String::from_utf8(head).unwrap())
+}
+
+/// Sends `frame` until the client, whose registration races the send, sees it.
+fn next_message(sink: &mut WebSocketSink, conn: &mut TcpStream, f: &ControlFrame) -> Vec<u8> {
+    conn.set_read_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    let mut header = [0u8; 2];
+    loop {
+        assert!(Instant::now() < deadline, "no frame received");
+        sink.send(f).unwrap();
+        if conn.read_exact(&mut header).is_ok() {
+            break;
+        }
+    }
+    let mut payload = vec![0u8; usize::from(header[1])];
+    conn.read_exact(&mut payload).unwrap();
+    [header.to_vec(), payload].concat()
+}
+
+#[test]
+fn handshake_answers_the_rfc_sample_key() {
+    let sink = sink();
+    let (_conn, head) = connect(&sink);
+    assert!(head.starts_with("HTTP/1.1 101 "), "{head}");
+    assert!(
+        head.contains(&format!("Sec-WebSocket-Accept: {ACCEPT}\r\n")),
+        "{head}"
+    );
+}
+
+#[test]
+fn frames_are_unmasked_json_text_messages() {
+    let mut sink = sink();
+    let (mut conn, _) = connect(&sink);
+    let frame = ControlFrame {
+        yaw: 0.5,
+        ..ControlFrame::NEUTRAL
+    };
+    let msg = next_message(&mut sink, &mut conn, &frame);
+    // FIN + text opcode, then an unmasked 7-bit length.
+    assert_eq!(msg[0], 0x81);
+    assert_eq!(usize::from(msg[1]), msg.len() - 2);
+    let json: serde_json::Value = serde_json::from_slice(&msg[2..]).unwrap();
+    assert_eq!(json["yaw"], 0.5);
+
+    sink.close(&ControlFrame::NEUTRAL).unwrap();
+    conn.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let mut rest = Vec::new();
+    conn.read_to_end(&mut rest).unwrap();
+    // Any frames still in flight, then an empty close frame.
+    assert!(rest.ends_with(&[0x88, 0x00]), "{rest:?}");
+}
+
+#[test]
+fn stalled_handshake_does_not_block_other_clients() {
+    let sink = sink();
+    let _stalled = TcpStream::connect(sink.local_addr()).unwrap();
+    let started = Instant::now();
+    let (_conn, head) = connect(&sink);
+    assert!(head.starts_with("HTTP/1.1 101 "));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}